@@ -1,7 +1,32 @@
-from quantum_animal_shogi import Environment
+import numpy as np
+import pickle
+
+from quantum_animal_shogi import Environment, RawEnvironment
 from pettingzoo.test import api_test
 
 
 env = Environment()
 env.reset()
 api_test(env, num_cycles=1_000, verbose_progress=False)
+
+
+# 勝敗が決した後のRawEnvironmentも、pickleで保存して復元できることを確認します。
+
+raw_env = RawEnvironment("classical")
+
+while not (raw_env.won() or raw_env.lost() or raw_env.draw()):
+    raw_env.step(int(np.flatnonzero(raw_env.observe("rows-v2")["action_mask"])[0]))
+
+restored_raw_env = pickle.loads(pickle.dumps(raw_env))
+
+assert restored_raw_env.record() == raw_env.record()
+assert restored_raw_env.outcome() == raw_env.outcome()
+
+# 勝敗が決した後のアクションは、受け付けません。
+
+try:
+    raw_env.step(int(np.flatnonzero(raw_env.observe("rows-v2")["action_mask"])[0]))
+except ValueError:
+    pass
+else:
+    raise AssertionError("step() accepted an action after the game was over")
@@ -0,0 +1,219 @@
+use std::{error, fmt};
+
+use crate::{State, bits};
+
+// マスです。手番側から見た盤面の右下が0、左上が11になります（Rust側の座標系）。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    // コンストラクタです。盤面の外ならNoneを返します。
+
+    pub fn new(bit: u8) -> Option<Square> {
+        (bit < 4 * 3).then_some(Square(bit))
+    }
+
+    // 行と列から作成します。行は手番側から見て手前が0、列は手番側から見て右が0です。
+
+    pub fn from_rank_file(rank: u8, file: u8) -> Option<Square> {
+        (rank < 4 && file < 3).then_some(Square(rank * 3 + file))
+    }
+
+    // ビットの位置を取得します。
+
+    pub fn bit(self) -> u8 {
+        self.0
+    }
+
+    // BitBoardを取得します。
+
+    pub fn bit_board(self) -> u16 {
+        1 << self.0
+    }
+
+    // 行を取得します。
+
+    pub fn rank(self) -> u8 {
+        self.0 / 3
+    }
+
+    // 列を取得します。
+
+    pub fn file(self) -> u8 {
+        self.0 % 3
+    }
+
+    // 盤面を回転した場合のマスを取得します。Python側の座標系（0は盤面の左上）との変換にも使用できます。
+
+    pub fn rotated(self) -> Square {
+        Square(4 * 3 - 1 - self.0)
+    }
+}
+
+// アクションです。Game::legal_actions()とGame::next_state()で使用している(u8, u8)のタプルと相互に変換できます。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Move { from: Square, to: Square },  // 駒を指す
+    Drop { hand_slot: u8, to: Square }  // 持ち駒を打つ（hand_slotは、手番側の持ち駒の中での順番です）
+}
+
+impl Action {
+    // Python側のアクションの数です。(4 * 3 + 8) * (4 * 3)で、240になります。
+
+    pub const INDEX_COUNT: usize = (4 * 3 + 8) * (4 * 3);
+
+    // Python側のインデックスから作成します。Python側では、アクションを(移動元（持ち駒は12 + hand_slot。Python側では、State::to_stable_action()で変換した駒の番号になります）) * 12 + 移動先のu16で表し、座標系は盤面の左上を0にしています。
+
+    pub fn from_index(index: usize) -> Option<Action> {
+        if index >= Action::INDEX_COUNT {
+            return None;
+        }
+
+        let to = Square((index % (4 * 3)) as u8).rotated();
+
+        match (index / (4 * 3)) as u8 {
+            from @ 0..12 => Some(Action::Move { from: Square(from).rotated(), to }),
+            hand_slot    => Some(Action::Drop { hand_slot: hand_slot - 4 * 3, to })
+        }
+    }
+
+    // Python側のインデックスを取得します。
+
+    pub fn index(self) -> usize {
+        match self {
+            Action::Move { from, to }      => from.rotated().bit() as usize * (4 * 3) + to.rotated().bit() as usize,
+            Action::Drop { hand_slot, to } => (4 * 3 + hand_slot) as usize * (4 * 3) + to.rotated().bit() as usize
+        }
+    }
+
+    // 移動先を取得します。
+
+    pub fn to(self) -> Square {
+        match self {
+            Action::Move { to, .. } => to,
+            Action::Drop { to, .. } => to
+        }
+    }
+}
+
+impl From<Action> for (u8, u8) {
+    fn from(action: Action) -> (u8, u8) {
+        match action {
+            Action::Move { from, to }      => (from.bit(), to.bit()),
+            Action::Drop { hand_slot, to } => (4 * 3 + hand_slot, to.bit())
+        }
+    }
+}
+
+impl TryFrom<(u8, u8)> for Action {
+    type Error = InvalidAction;
+
+    fn try_from(action: (u8, u8)) -> Result<Action, InvalidAction> {
+        let to = Square::new(action.1).ok_or(InvalidAction(action))?;
+
+        match action.0 {
+            from @ 0..12  => Ok(Action::Move { from: Square(from), to }),
+            slot @ 12..20 => Ok(Action::Drop { hand_slot: slot - 4 * 3, to }),
+            _             => Err(InvalidAction(action))
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Move { from, to }      => write!(f, "{}-{}", from.bit(), to.bit()),
+            Action::Drop { hand_slot, to } => write!(f, "*{}-{}", hand_slot, to.bit())
+        }
+    }
+}
+
+// 持ち駒を打つアクションの、安定した表現です。
+//
+// (u8, u8)のタプルでは、持ち駒を打つアクションの移動元は12 + hand_slot（手番側の持ち駒の中での順番）なので、持ち駒が増えたり減ったりすると同じ駒でも番号が変わってしまいます。安定した表現では、移動元を12 + 駒の番号（State::piece_id()）にします。駒を指すアクションは、(u8, u8)のタプルと同じです。
+//
+// 安定した表現もAction::try_from()でActionに変換できて（Action::Dropのhand_slotが駒の番号になります）、Action::index()でPython側のインデックスにできます。
+
+impl State {
+    // 手番側から見た駒の番号を取得します。手番側由来の駒が0〜3、敵由来の駒が4〜7で、持ち駒が増えたり減ったりしても変わりません。
+
+    pub fn piece_id(&self, index: usize) -> u8 {
+        (if self.turn.is_multiple_of(2) { index } else { index ^ 4 }) as u8
+    }
+
+    // アクションを、安定した表現に変換します。hand_slotの持ち駒がない場合は、Noneを返します。
+
+    pub fn to_stable_action(&self, action: (u8, u8)) -> Option<(u8, u8)> {
+        if action.0 < 4 * 3 {
+            return Some(action);
+        }
+
+        let index = bits(self.ownership).filter(|index| self.bit_boards[*index] == 0).nth(action.0 as usize - 4 * 3)?;
+
+        Some((4 * 3 + self.piece_id(index), action.1))
+    }
+
+    // 安定した表現を、アクションに変換します。駒が手番側の持ち駒ではない場合は、Noneを返します。
+
+    pub fn from_stable_action(&self, stable_action: (u8, u8)) -> Option<(u8, u8)> {
+        if stable_action.0 < 4 * 3 {
+            return Some(stable_action);
+        }
+
+        let index = self.piece_id(stable_action.0.checked_sub(4 * 3).filter(|piece_id| *piece_id < 8)? as usize) as usize;  // 変換は、逆変換と同じ計算です。
+
+        let hand_slot = bits(self.ownership).filter(|index| self.bit_boards[*index] == 0).position(|other_index| other_index == index)?;
+
+        Some(((4 * 3 + hand_slot) as u8, stable_action.1))
+    }
+}
+
+// (u8, u8)のタプルがアクションとして解釈できない場合のエラーです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidAction(pub (u8, u8));
+
+impl fmt::Display for InvalidAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid action: ({}, {})", self.0.0, self.0.1)
+    }
+}
+
+impl error::Error for InvalidAction {}
+
+// 現在の状態では実行できないアクションの場合のエラーです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalAction {
+    Invalid(InvalidAction),                       // アクションとして解釈できない
+    EmptySquare(Square),                          // 移動元に駒がない
+    OpponentPiece(Square),                        // 移動元の駒が敵の駒
+    OccupiedSquare(Square),                       // 移動先に自分の駒がある（持ち駒を打つ場合は、移動先に駒がある）
+    Unreachable { from: Square, to: Square },     // 駒のどの可能性でも移動先に移動できない
+    BadHandSlot(u8),                              // 持ち駒の順番が持ち駒の数を超えている
+    ForbiddenDrop { hand_slot: u8, to: Square }   // ルール（RuleSet）で打つことが禁止されている
+}
+
+impl From<InvalidAction> for IllegalAction {
+    fn from(error: InvalidAction) -> IllegalAction {
+        IllegalAction::Invalid(error)
+    }
+}
+
+impl fmt::Display for IllegalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalAction::Invalid(error)                  => write!(f, "{}", error),
+            IllegalAction::EmptySquare(square)             => write!(f, "illegal action: no piece on square {}", square.bit()),
+            IllegalAction::OpponentPiece(square)           => write!(f, "illegal action: piece on square {} belongs to the opponent", square.bit()),
+            IllegalAction::OccupiedSquare(square)          => write!(f, "illegal action: square {} is occupied", square.bit()),
+            IllegalAction::Unreachable { from, to }        => write!(f, "illegal action: no possibility of the piece on square {} can move to square {}", from.bit(), to.bit()),
+            IllegalAction::BadHandSlot(hand_slot)          => write!(f, "illegal action: no hand piece in slot {}", hand_slot),
+            IllegalAction::ForbiddenDrop { hand_slot, to } => write!(f, "illegal action: the rule set forbids dropping the hand piece in slot {} on square {}", hand_slot, to.bit())
+        }
+    }
+}
+
+impl error::Error for IllegalAction {}
@@ -0,0 +1,73 @@
+use std::{env, process::exit, time::Instant};
+
+use quantum_animal_shogi_core::{Game, State, Variant};
+
+// perftのコマンドラインです。
+//
+// ```text
+// perft [--classical] [--state "<局面の文字列表記>"] [--divide] <深さ>
+// ```
+//
+// * --classicalを指定すると、どうぶつしょうぎの初期状態から数えます。
+// * --stateを指定すると、その局面から数えます。
+// * --divideを指定すると、ルートのアクションごとの数も出力します。
+
+const USAGE: &str = "usage: perft [--classical] [--state \"<notation>\"] [--divide] <depth>";
+
+fn main() {
+    let mut variant = Variant::Quantum;
+    let mut state = None;
+    let mut divide = false;
+    let mut depth = None;
+
+    // 引数を解析します。
+
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--classical" => variant = Variant::Classical,
+            "--divide"    => divide = true,
+            "--state"     => {
+                let notation = args.next().unwrap_or_else(|| usage());
+
+                state = Some(notation.parse::<State>().unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    exit(2);
+                }));
+            }
+            _             => depth = Some(arg.parse::<u32>().unwrap_or_else(|_| usage()))
+        }
+    }
+
+    let state = state.unwrap_or_else(|| variant.initial_state());
+    let depth = depth.unwrap_or_else(|| usage());
+
+    // 数えて、出力します。
+
+    let now = Instant::now();
+
+    let count = if divide {
+        let counts = Game::perft_divide(&state, depth);
+
+        for (action, count) in &counts {
+            println!("{}: {}", state.action_notation((*action).into()), count);
+        }
+
+        println!();
+
+        counts.into_iter().map(|(_, count)| count).sum()
+    } else {
+        Game::perft(&state, depth)
+    };
+
+    println!("nodes: {}", count);
+    println!("time:  {:.3}s", now.elapsed().as_secs_f64());
+}
+
+// 使い方を出力して、終了します。
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(2);
+}
@@ -0,0 +1,223 @@
+use std::{error, fmt, str::FromStr};
+
+use crate::{State, bits};
+
+// 観測結果（ニューラル・ネットワーク等に入力するための、f32の配列）への変換です。PythonやTypeScriptで同じ変換を書くと、片方だけ変更して壊れてしまうので、ここにまとめます。
+//
+// レイアウトには名前とバージョンを付けて、レイアウトを変更する場合は新しいバージョンを追加します（既存のレイアウトは変更しません）。
+//
+// どのレイアウトでも、駒の特徴は以下の9個の値で表します。
+//
+// * 0〜4: 駒の可能性（ひよこ、きりん、ぞう、ライオン、にわとり）
+// * 5〜6: 由来（先手、後手）
+// * 7〜8: 所有者（手番側、敵）
+
+const FEATURE_COUNT: usize = 5 + 2 + 2;
+
+// 観測結果のレイアウトです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ObservationLayout {
+    RowsV1,   // "rows-v1"。[20, 9]。0〜11行目は盤面のマス（手番側から見て左上が0）、12行目からは手番側の持ち駒、19行目からは逆順に敵の持ち駒（どちらもインデックスの順）。最初のバージョンのobservation()と同じです。持ち駒の駒の番号がわからないので、decode()したステートの持ち駒を打つアクションは元のステートと一致しません
+    RowsV2,   // "rows-v2"。[20, 9]。0〜11行目は盤面のマス（手番側から見て左上が0）、12〜19行目は持ち駒（12 + 駒の番号（State::piece_id()）の行）
+    PlanesV1  // "planes-v1"。[81, 4, 3]。CNN向けに、0〜8チャンネルは盤面のマスの特徴、9〜80チャンネルは持ち駒（駒の番号ごとに9チャンネル）の特徴を全てのマスに複製したもの
+}
+
+impl ObservationLayout {
+    // 全てのレイアウトです。
+
+    pub const ALL: [ObservationLayout; 3] = [ObservationLayout::RowsV1, ObservationLayout::RowsV2, ObservationLayout::PlanesV1];
+
+    // 名前を取得します。
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ObservationLayout::RowsV1   => "rows-v1",
+            ObservationLayout::RowsV2   => "rows-v2",
+            ObservationLayout::PlanesV1 => "planes-v1"
+        }
+    }
+
+    // 形を取得します。
+
+    pub fn shape(self) -> &'static [usize] {
+        match self {
+            ObservationLayout::RowsV1 | ObservationLayout::RowsV2 => &[4 * 3 + 8, FEATURE_COUNT],
+            ObservationLayout::PlanesV1                          => &[FEATURE_COUNT + 8 * FEATURE_COUNT, 4, 3]
+        }
+    }
+
+    // 要素の数を取得します。
+
+    pub fn size(self) -> usize {
+        self.shape().iter().product()
+    }
+
+    // 持ち駒の行が、駒の番号で決まる（持ち駒が増えたり減ったりしても変わらない）かを取得します。
+
+    fn has_stable_hand_rows(self) -> bool {
+        self != ObservationLayout::RowsV1
+    }
+
+    // ステートを、観測結果（形がshape()の配列を、行優先で1次元にしたもの）に変換します。
+
+    pub fn encode(self, state: &State) -> Vec<f32> {
+        let rows = encode_rows(state, self.has_stable_hand_rows());
+
+        match self {
+            ObservationLayout::RowsV1 | ObservationLayout::RowsV2 => rows.into_iter().flatten().collect(),
+            ObservationLayout::PlanesV1                          => {
+                let board_planes = (0..FEATURE_COUNT).flat_map(|feature| rows[0..4 * 3].iter().map(move |row| row[feature]));
+                let hand_planes = rows[4 * 3..].iter().flat_map(|row| row.iter().flat_map(|value| [*value; 4 * 3]));
+
+                board_planes.chain(hand_planes).collect()
+            }
+        }
+    }
+
+    // 観測結果を、ステートに変換します。盤面の駒のインデックスは観測結果に含まれないので、由来ごとに、持ち駒が使っていないインデックスを盤面のマスの順に割り当てます。"rows-v1"の持ち駒は、盤面の駒の後に、エンコードした時と同じ順序になるように割り当てます。手番は観測結果に含まれないので、引数で指定してください。
+
+    pub fn decode(self, observation: &[f32], turn: u16) -> Result<State, DecodeObservationError> {
+        if observation.len() != self.size() {
+            return Err(DecodeObservationError::Length { expected: self.size(), actual: observation.len() });
+        }
+
+        let rows = match self {
+            ObservationLayout::RowsV1 | ObservationLayout::RowsV2 => observation.chunks(FEATURE_COUNT).map(|row| row.try_into().unwrap()).collect::<Vec<[f32; FEATURE_COUNT]>>(),
+            ObservationLayout::PlanesV1                          => {
+                let board_rows = (0..4 * 3).map(|i| [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|feature| observation[feature * 4 * 3 + i]));
+                let hand_rows = (0..8).map(|i| [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|feature| observation[(FEATURE_COUNT + i * FEATURE_COUNT + feature) * 4 * 3]));
+
+                board_rows.chain(hand_rows).collect()
+            }
+        };
+
+        decode_rows(&rows, turn, self.has_stable_hand_rows())
+    }
+}
+
+// 観測結果の行（マスか持ち駒ごとの、駒の特徴）を作成します。
+
+fn encode_rows(state: &State, stable_hand_rows: bool) -> [[f32; FEATURE_COUNT]; 4 * 3 + 8] {
+    let mut result = [[0.0; FEATURE_COUNT]; 4 * 3 + 8];
+
+    for index in 0..8 {
+        // 盤面の駒は手番側から見て左上を0にしたマスの行に入れます。持ち駒は、stable_hand_rowsなら12 + 駒の番号の行（持ち駒を打つアクションの安定した表現と対応させます）、そうでなければ、所有者ごとに詰めた行に入れます。
+
+        let owned = state.ownership & 1 << index != 0;
+
+        let row = match state.bit_boards[index] {
+            0 if stable_hand_rows => &mut result[4 * 3 + state.piece_id(index) as usize],
+            0                     => {
+                let i = (0..index).filter(|other| state.bit_boards[*other] == 0 && (state.ownership & 1 << other != 0) == owned).count();
+
+                &mut result[if owned { 4 * 3 + i } else { 4 * 3 + 8 - 1 - i }]
+            }
+            bit_board             => &mut result[4 * 3 - 1 - bit_board.trailing_zeros() as usize]
+        };
+
+        for piece_bit in bits(state.pieces[index]) {
+            row[piece_bit] = 1.0;
+        }
+
+        row[5 + if index < 4 { 0 } else { 1 }] = 1.0;
+        row[5 + 2 + if owned { 0 } else { 1 }] = 1.0;
+    }
+
+    result
+}
+
+// 観測結果の行を、ステートに変換します。
+
+fn decode_rows(rows: &[[f32; FEATURE_COUNT]], turn: u16, stable_hand_rows: bool) -> Result<State, DecodeObservationError> {
+    let mut pieces = [0; 8];
+    let mut ownership = 0;
+    let mut bit_boards = [0; 8];
+
+    // 駒を配置します。stable_hand_rowsなら、持ち駒は行から駒の番号がわかるので、そのインデックスに配置します（State::piece_id()は、逆変換も同じ計算です）。盤面の駒と、stable_hand_rowsではない場合の持ち駒は、由来が同じ駒の空いているインデックスに順に配置します。
+
+    let is_piece = |i: &usize| rows[*i][5..5 + 2].contains(&1.0);
+
+    let stable_hand_indices = (4 * 3..4 * 3 + 8).filter(|_| stable_hand_rows).filter(is_piece).map(|i| (i, Some(if turn.is_multiple_of(2) { i - 4 * 3 } else { (i - 4 * 3) ^ 4 })));
+    let board_indices = (0..4 * 3).filter(is_piece).map(|i| (i, None));
+    let hand_indices = (4 * 3..4 * 3 + 8).filter(|i| rows[*i][5 + 2] == 1.0).chain((4 * 3..4 * 3 + 8).rev().filter(|i| rows[*i][5 + 2 + 1] == 1.0)).filter(|_| !stable_hand_rows).filter(is_piece).map(|i| (i, None));
+
+    let mut placed = 0_u8;
+
+    for (i, index) in stable_hand_indices.chain(board_indices).chain(hand_indices) {
+        let origin = if rows[i][5] == 1.0 { 0 } else { 4 };
+
+        let index = match index {
+            Some(index) => index,
+            None        => (origin..origin + 4).find(|index| placed & 1 << index == 0).ok_or(DecodeObservationError::PieceCount)?
+        };
+
+        if index / 4 * 4 != origin || placed & 1 << index != 0 {
+            return Err(DecodeObservationError::PieceCount);
+        }
+
+        placed |= 1 << index;
+
+        pieces[index] = (0..5).filter(|piece_bit| rows[i][*piece_bit] == 1.0).fold(0, |acc, piece_bit| acc | 1 << piece_bit);
+
+        if rows[i][5 + 2] == 1.0 {
+            ownership |= 1 << index;
+        }
+
+        if i < 4 * 3 {
+            bit_boards[index] = 1 << (4 * 3 - 1 - i);
+        }
+    }
+
+    if placed != 0xff {
+        return Err(DecodeObservationError::PieceCount);
+    }
+
+    Ok(State::new(pieces, ownership, bit_boards, turn))
+}
+
+impl fmt::Display for ObservationLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ObservationLayout {
+    type Err = UnknownLayout;
+
+    fn from_str(s: &str) -> Result<ObservationLayout, UnknownLayout> {
+        ObservationLayout::ALL.into_iter().find(|layout| layout.name() == s).ok_or_else(|| UnknownLayout(s.to_string()))
+    }
+}
+
+// レイアウトの名前が不正な場合のエラーです。
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownLayout(pub String);
+
+impl fmt::Display for UnknownLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown observation layout \"{}\" (expected one of: {})", self.0, ObservationLayout::ALL.map(ObservationLayout::name).join(", "))
+    }
+}
+
+impl error::Error for UnknownLayout {}
+
+// 観測結果をステートに変換できない場合のエラーです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeObservationError {
+    Length { expected: usize, actual: usize },  // 要素の数が、レイアウトと異なる
+    PieceCount                                  // 先手由来の駒と後手由来の駒が、4つずつではない
+}
+
+impl fmt::Display for DecodeObservationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeObservationError::Length { expected, actual } => write!(f, "invalid observation: expected {} values, got {}", expected, actual),
+            DecodeObservationError::PieceCount                  => write!(f, "invalid observation: expected 4 pieces of each origin")
+        }
+    }
+}
+
+impl error::Error for DecodeObservationError {}
@@ -0,0 +1,93 @@
+use crate::{DrawReason, GameRecord, IllegalAction, Outcome, RuleSet, State};
+
+// 局面の履歴付きのゲームです。Stateに履歴を持たせると大きくなってしまうので、履歴は局面のハッシュ値（State::hash）だけを別に保持します。
+
+#[derive(Clone)]
+pub struct GameHistory {
+    state: State,
+    hashes: Vec<u64>,    // これまでに現れた局面のハッシュ値（現在の局面を含みます）
+    record: GameRecord,  // 棋譜
+    rules: RuleSet       // ルール
+}
+
+impl GameHistory {
+    // コンストラクタです。標準のルール（RuleSet::STANDARD）を使用します。
+
+    pub fn new(state: State) -> Self {
+        Self::with_rules(state, RuleSet::STANDARD)
+    }
+
+    // ルールを指定して作成します。
+
+    pub fn with_rules(state: State, rules: RuleSet) -> Self {
+        Self {
+            state,
+            hashes: vec![state.hash],
+            record: GameRecord::new(state),
+            rules
+        }
+    }
+
+    // 現在の局面を取得します。
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // 局面のハッシュ値の履歴を取得します。
+
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    // 棋譜を取得します。
+
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    // ルールを取得します。
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    // アクションを実行して、局面を進めます。
+
+    pub fn play(&mut self, action: (u8, u8)) {
+        self.state = self.rules.next_state(&self.state, action);
+        self.hashes.push(self.state.hash);
+        self.record.actions.push(action);
+    }
+
+    // アクションが合法手であることをチェックしてから、局面を進めます。
+
+    pub fn try_play(&mut self, action: (u8, u8)) -> Result<(), IllegalAction> {
+        self.state = self.rules.try_next_state(&self.state, action)?;
+        self.hashes.push(self.state.hash);
+        self.record.actions.push(action);
+
+        Ok(())
+    }
+
+    // 同一局面が規定回数現れて、千日手になったかを取得します。駒の可能性（pieces）も局面に含むので、可能性が収束（収縮？）した局面は別の局面になります。
+
+    pub fn repetition(&self) -> bool {
+        if self.rules.repetition_count == 0 {
+            return false;
+        }
+
+        let hash = self.hashes.last().unwrap();
+
+        self.hashes.iter().rev().step_by(2).filter(|other| *other == hash).count() >= self.rules.repetition_count  // 手番が同じ局面だけを比較します。
+    }
+
+    // 手番側から見たゲームの結果を取得します。Stateだけで判定できる結果に加えて、千日手を判定します。
+
+    pub fn outcome(&self) -> Outcome {
+        match self.rules.outcome(&self.state) {
+            Outcome::Ongoing if self.repetition() => Outcome::Draw(DrawReason::Repetition),
+            outcome                               => outcome
+        }
+    }
+}
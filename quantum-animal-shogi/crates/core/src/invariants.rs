@@ -0,0 +1,95 @@
+use std::{error, fmt};
+
+use crate::State;
+
+// ステートが満たすべき条件（不変条件）のチェックです。テストや、外部から受け取ったステートの検証に使用します。
+
+impl State {
+    // 不変条件を満たしているかをチェックし、満たしていない場合は最初に見つかった違反をエラーとして返します。
+
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        // 駒の可能性をチェックします。
+
+        for (index, piece) in self.pieces.into_iter().enumerate() {
+            if piece == 0 || piece & !0b1_1111 != 0 {
+                return Err(InvariantViolation::InvalidPiece(index));
+            }
+
+            // 成った駒（「にわとり」）は「ひよこ」の可能性から作られるので、「ひよこ」と「にわとり」の可能性を同時に持つことはありません。
+
+            if piece & 0b1_0001 == 0b1_0001 {
+                return Err(InvariantViolation::ChickAndHen(index));
+            }
+
+            // 取られた駒は元に戻るので、持ち駒は成っていません。
+
+            if self.bit_boards[index] == 0 && piece & 0b1_0000 != 0 {
+                return Err(InvariantViolation::PromotedInHand(index));
+            }
+        }
+
+        // 駒の位置をチェックします。
+
+        for (index, bit_board) in self.bit_boards.into_iter().enumerate() {
+            if bit_board.count_ones() > 1 || bit_board >> (4 * 3) != 0 {
+                return Err(InvariantViolation::InvalidBitBoard(index));
+            }
+
+            if let Some(other_index) = (0..index).find(|other_index| self.bit_boards[*other_index] & bit_board != 0) {
+                return Err(InvariantViolation::OverlappingBitBoards(other_index, index));
+            }
+        }
+
+        // 由来が同じ4駒が、それぞれの動物を1つずつ持つことをチェックします。「使い切り」による収束（収縮？）が正しければ、全ての可能性がどれかの可能世界で実現します。
+
+        let feasible_pieces = self.feasible_pieces();
+
+        if feasible_pieces.contains(&0) {
+            return Err(InvariantViolation::NoPossibleWorld);
+        }
+
+        if let Some(index) = (0..8).find(|index| feasible_pieces[*index] != self.pieces[*index]) {
+            return Err(InvariantViolation::Uncollapsed { index, infeasible: self.pieces[index] & !feasible_pieces[index] });
+        }
+
+        // ハッシュ値をチェックします。
+
+        if self.hash != self.compute_hash() {
+            return Err(InvariantViolation::Hash);
+        }
+
+        Ok(())
+    }
+}
+
+// 不変条件の違反です。indexはState::piecesやState::bit_boardsのインデックスです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    InvalidPiece(usize),                           // 駒の可能性が空か、不正なビットが立っている
+    ChickAndHen(usize),                            // 「ひよこ」と「にわとり」の可能性を同時に持っている
+    PromotedInHand(usize),                         // 持ち駒が成っている
+    InvalidBitBoard(usize),                        // 駒の位置に、複数のビットか盤面外のビットが立っている
+    OverlappingBitBoards(usize, usize),            // 複数の駒が同じマスにある
+    NoPossibleWorld,                               // 可能世界がない（由来が同じ4駒に、動物を1つずつ割り当てられない）
+    Uncollapsed { index: usize, infeasible: u8 },  // どの可能世界でも実現しない可能性が残っている
+    Hash                                           // ハッシュ値が、最初から計算した値と異なる
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::InvalidPiece(index)                => write!(f, "piece {} has an empty or invalid possibility set", index),
+            InvariantViolation::ChickAndHen(index)                 => write!(f, "piece {} can be both a chick and a hen", index),
+            InvariantViolation::PromotedInHand(index)              => write!(f, "piece {} is promoted in hand", index),
+            InvariantViolation::InvalidBitBoard(index)             => write!(f, "piece {} has an invalid bit board", index),
+            InvariantViolation::OverlappingBitBoards(index, other) => write!(f, "pieces {} and {} are on the same square", index, other),
+            InvariantViolation::NoPossibleWorld                    => write!(f, "no assignment of animals is consistent with the possibility sets"),
+            InvariantViolation::Uncollapsed { index, infeasible }  => write!(f, "piece {} keeps possibilities {:#07b} that no possible world realizes", index, infeasible),
+            InvariantViolation::Hash                               => write!(f, "hash does not match the recomputed hash")
+        }
+    }
+}
+
+impl error::Error for InvariantViolation {}
+
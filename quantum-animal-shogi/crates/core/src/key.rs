@@ -0,0 +1,138 @@
+use std::{error, fmt};
+
+use arrayvec::ArrayVec;
+
+use crate::{InvariantViolation, State};
+
+// 局面のコンパクトなキー（u128）です。置換表や定跡、局面の重複除去で、同じ局面を同じキーにするために使用します。
+//
+// キーは手番側から見た局面を表し、手数と駒のインデックスは含みません（駒のインデックスが入れ替わっただけの局面は、同じキーになります）。
+//
+// * 0〜83ビット: 盤面。マス（ビット・ボードのビットの位置）ごとに7ビットで、マスnの駒は7 * nビット目からになります。駒がないマスは0です。
+//   * 0〜4ビット: 駒の可能性（ひよこ、きりん、ぞう、ライオン、にわとり）
+//   * 5ビット: 後手由来で、かつ駒が確定していない場合に1
+//   * 6ビット: 敵の駒の場合に1
+// * 84〜125ビット: 持ち駒。持ち駒ごとに6ビットで、値の昇順に並べて84 + 6 * iビット目からにします。持ち駒が7つに満たない場合、残りは0です。
+//   * 0〜3ビット: 駒の可能性（ひよこ、きりん、ぞう、ライオン。持ち駒は成っていないので、にわとりはありません）
+//   * 4ビット: 後手由来で、かつ駒が確定していない場合に1
+//   * 5ビット: 敵の駒の場合に1
+//
+// 確定した駒の由来は、駒の動きにも収束（収縮？）にも影響しないので、キーに含めません。持ち駒は、どちらかのライオンが盤面に残っている限り7つ以下です。
+
+const SQUARE_BITS: u32 = 7;
+const HAND_BITS: u32 = 6;
+const HAND_OFFSET: u32 = SQUARE_BITS * 4 * 3;
+
+impl State {
+    // コンパクトなキーを取得します。
+
+    pub fn compact_key(&self) -> u128 {
+        let origin_bit = |index: usize| if index >= 4 && self.pieces[index].count_ones() != 1 { 1 } else { 0 };
+        let enemy_bit = |index: usize| if self.ownership & 1 << index != 0 { 0 } else { 1 };
+
+        let board = (0..8)
+            .filter(|index| self.bit_boards[*index] != 0)
+            .fold(0, |acc, index| acc | (self.pieces[index] as u128 | origin_bit(index) << 5 | enemy_bit(index) << 6) << (SQUARE_BITS * self.bit_boards[index].trailing_zeros()));
+
+        let mut hands = (0..8)
+            .filter(|index| self.bit_boards[*index] == 0)
+            .map(|index| self.pieces[index] as u128 | origin_bit(index) << 4 | enemy_bit(index) << 5)
+            .collect::<ArrayVec<_, 8>>();
+
+        hands.sort();
+
+        hands.into_iter().enumerate().fold(board, |acc, (i, hand)| acc | hand << (HAND_OFFSET + HAND_BITS * i as u32))
+    }
+
+    // 左右反転も同じ局面とみなした、正規化したキーを取得します。コンパクトなキーと、左右反転した局面のコンパクトなキーの小さい方です。
+
+    pub fn canonical_key(&self) -> u128 {
+        self.compact_key().min(self.mirrored().compact_key())
+    }
+
+    // キーから、ステートを作成します。キーに含まれない手数は、引数で指定してください。正規化したキーの場合は、左右反転したどちらかの局面になります。
+
+    pub fn from_compact_key(key: u128, turn: u16) -> Result<State, DecodeKeyError> {
+        // キーから、駒（駒の可能性、後手由来で確定していないか、自分の駒か、ビット・ボード）を取得します。
+
+        let squares = (0..4 * 3)
+            .map(|bit| (key >> (SQUARE_BITS * bit) & 0b111_1111) as u8)
+            .enumerate()
+            .filter(|(_, square)| *square != 0)
+            .map(|(bit, square)| (square & 0b1_1111, square & 1 << 5 != 0, square & 1 << 6 == 0, 1 << bit));
+
+        let hands = (0..(128 - HAND_OFFSET) / HAND_BITS)
+            .map(|i| (key >> (HAND_OFFSET + HAND_BITS * i) & 0b11_1111) as u8)
+            .take_while(|hand| *hand != 0)
+            .map(|hand| (hand & 0b_1111, hand & 1 << 4 != 0, hand & 1 << 5 == 0, 0));
+
+        let pieces = squares.chain(hands).collect::<ArrayVec<_, 20>>();
+
+        if pieces.len() != 8 {
+            return Err(DecodeKeyError::PieceCount(pieces.len()));
+        }
+
+        // 由来ごとに、駒のインデックスを割り当てます。確定していない駒は、キーの由来の通りにします。確定した駒は、由来が同じ確定していない駒が持たない動物（「ひよこ」と「にわとり」は同じ動物とみなします）を、まだ持っていない方の由来にします。
+
+        let animals = |piece: u8| (piece | piece >> 4) & 0b_1111;
+
+        let mut missing_animals = [false, true].map(|second| {
+            pieces.iter().filter(|(piece, origin, ..)| piece.count_ones() != 1 && *origin == second).fold(0b_1111, |acc, (piece, ..)| acc & !animals(*piece))
+        });
+
+        let mut groups: [ArrayVec<_, 8>; 2] = [ArrayVec::new(), ArrayVec::new()];
+
+        for piece in &pieces {
+            let group = match piece.0.count_ones() {
+                1 => (0..2).find(|group| missing_animals[*group] & animals(piece.0) != 0).ok_or(DecodeKeyError::Origin)?,
+                _ => piece.1 as usize
+            };
+
+            missing_animals[group] &= !animals(piece.0);
+            groups[group].push(*piece);
+        }
+
+        if groups.iter().any(|group| group.len() != 4) {
+            return Err(DecodeKeyError::Origin);
+        }
+
+        // ステートを作成して、不変条件をチェックします。
+
+        let mut result_pieces = [0; 8];
+        let mut ownership = 0;
+        let mut bit_boards = [0; 8];
+
+        for (index, (piece, _, owned, bit_board)) in groups.into_iter().flatten().enumerate() {
+            result_pieces[index] = piece;
+            ownership |= if owned { 1 << index } else { 0 };
+            bit_boards[index] = bit_board;
+        }
+
+        let result = State::new(result_pieces, ownership, bit_boards, turn);
+
+        result.check_invariants().map_err(DecodeKeyError::Invariant)?;
+
+        Ok(result)
+    }
+}
+
+// キーからステートを作成できない場合のエラーです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeKeyError {
+    PieceCount(usize),             // 駒の数が8ではない
+    Origin,                        // 由来が同じ4駒に、動物を1つずつ割り当てられない
+    Invariant(InvariantViolation)  // 作成したステートが、不変条件を満たさない
+}
+
+impl fmt::Display for DecodeKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeKeyError::PieceCount(count)    => write!(f, "invalid key: expected 8 pieces, got {}", count),
+            DecodeKeyError::Origin               => write!(f, "invalid key: pieces cannot be split into two origins of 4 distinct animals"),
+            DecodeKeyError::Invariant(violation) => write!(f, "invalid key: {}", violation)
+        }
+    }
+}
+
+impl error::Error for DecodeKeyError {}
@@ -1,11 +1,40 @@
-use std::{fmt, iter::{once, repeat}, ops::{BitAndAssign, BitOr}, sync::LazyLock};
+use std::{fmt, iter::once, ops::BitAndAssign, sync::LazyLock};
 
 use arrayvec::ArrayVec;
 use itertools::Itertools;
 use num_traits::PrimInt;
 use unicode_width::UnicodeWidthStr;
 
-const MAX_TURN: u16 = 256;
+mod action;
+mod encoding;
+mod history;
+mod invariants;
+mod key;
+mod measurement;
+mod notation;
+mod outcome;
+mod perft;
+mod record;
+mod rules;
+mod symmetry;
+mod trace;
+mod undo;
+mod worlds;
+mod zobrist;
+
+pub use action::{Action, IllegalAction, InvalidAction, Square};
+pub use encoding::{DecodeObservationError, ObservationLayout, UnknownLayout};
+pub use history::GameHistory;
+pub use invariants::InvariantViolation;
+pub use key::DecodeKeyError;
+pub use measurement::MeasurementError;
+pub use notation::{Notation, ParseStateError};
+pub use outcome::{DrawReason, Outcome, Reason};
+pub use record::{GameRecord, ParseRecordError, ReplayError};
+pub use rules::{RuleSet, TryRule};
+pub use trace::CollapseEvent;
+pub use undo::Undo;
+pub use worlds::Marginals;
 
 // 立っているビットの位置のイテレーターを取得します。
 
@@ -34,10 +63,8 @@ static NEXTS: LazyLock<[[u16; 4 * 3]; 5]> = LazyLock::new(|| {  // [Piece、ビ
     let w = |bit_board| (bit_board & 0b_110_110_110_110) >> 1;
 
     (0..5)
-        .into_iter()
         .map(|piece_bit| {
             (0..4 * 3)
-                .into_iter()
                 .map(|bit_board_bit| (1 << bit_board_bit) as u16)
                 .map(|bit_board| match piece_bit {
                     0 /* ひよこ   */ => n(bit_board),
@@ -56,12 +83,73 @@ static NEXTS: LazyLock<[[u16; 4 * 3]; 5]> = LazyLock::new(|| {  // [Piece、ビ
 
 // ゲームの状態です。
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct State {
     pub pieces: [u8; 8],       // 駒（先手由来×4 + 後手由来×4）
     pub ownership: u8,         // 駒を所有しているか
     pub bit_boards: [u16; 8],  // 駒単位の盤面（持ち駒は、対応するbit_boardが0になります）
-    pub turn: u16              // 手数
+    pub turn: u16,             // 手数
+    pub hash: u64              // Zobristハッシュ（手数は含みませんが、手番は含みます）
+}
+
+impl State {
+    // コンストラクタです。ハッシュ値を計算します。
+
+    pub fn new(pieces: [u8; 8], ownership: u8, bit_boards: [u16; 8], turn: u16) -> State {
+        let mut result = State { pieces, ownership, bit_boards, turn, hash: 0 };
+
+        result.hash = zobrist::hash(&result);
+
+        result
+    }
+
+    // 全ての駒が確定している（どうぶつしょうぎの状態になっている）かを取得します。
+
+    pub fn is_classical(&self) -> bool {
+        self.pieces.iter().all(|piece| piece.count_ones() == 1)
+    }
+
+    // ハッシュ値を最初から計算します。フィールドを直接変更した場合は、この値をhashに設定してください。
+
+    pub fn compute_hash(&self) -> u64 {
+        zobrist::hash(self)
+    }
+
+    // 持ち駒を打つアクションを、同じ結果になる代表のアクションに変換します。由来と駒の可能性が同じ持ち駒は、どれを打っても同じ結果（駒のインデックスが入れ替わっただけのステート）になるので、その中で最初の持ち駒を打つアクションを代表にします。駒を指すアクションは、そのまま返します。hand_slotの持ち駒がない場合は、Noneを返します。
+
+    pub fn representative_action(&self, action: (u8, u8)) -> Option<(u8, u8)> {
+        if action.0 < 4 * 3 {
+            return Some(action);
+        }
+
+        let hand_indices = bits(self.ownership).filter(|index| self.bit_boards[*index] == 0).collect::<ArrayVec<_, 8>>();
+        let index = *hand_indices.get(action.0 as usize - 4 * 3)?;
+
+        let hand_slot = hand_indices.iter().position(|other_index| other_index / 4 == index / 4 && self.pieces[*other_index] == self.pieces[index]).unwrap();
+
+        Some(((4 * 3 + hand_slot) as u8, action.1))
+    }
+
+    // ハッシュ値を更新しながら、駒の可能性を設定します。
+
+    fn set_piece(&mut self, index: usize, piece: u8) {
+        self.hash ^= zobrist::piece_key(index, self.pieces[index]) ^ zobrist::piece_key(index, piece);
+        self.pieces[index] = piece;
+    }
+
+    // ハッシュ値を更新しながら、駒の位置を設定します。
+
+    fn set_bit_board(&mut self, index: usize, bit_board: u16) {
+        self.hash ^= zobrist::bit_board_key(index, self.bit_boards[index], self.turn) ^ zobrist::bit_board_key(index, bit_board, self.turn);
+        self.bit_boards[index] = bit_board;
+    }
+
+    // ハッシュ値を更新しながら、駒の所有者を入れ替えます。
+
+    fn toggle_ownership(&mut self, index: usize) {
+        self.hash ^= zobrist::ownership_key(index);
+        self.ownership ^= 1 << index;
+    }
 }
 
 impl fmt::Display for State {
@@ -95,12 +183,10 @@ impl fmt::Display for State {
                 .chain(once("-".repeat((2 + 11 + 2) * 3 + 2 * 2).to_string()))
                 .chain(
                     (0..4)
-                        .into_iter()
                         .map(|r| {
                             (0..3)
-                                .into_iter()
                                 .map(|c| {
-                                    let Some(index) = self.bit_boards.iter().position(|bit_board| bit_board & 1 << r * 3 + c != 0) else {
+                                    let Some(index) = self.bit_boards.iter().position(|bit_board| bit_board & 1 << (r * 3 + c) != 0) else {
                                         return " ".repeat(2 + 11 + 2).to_string();
                                     };
 
@@ -122,78 +208,68 @@ impl fmt::Display for State {
     }
 }
 
-// ゲームのルールです。
+// ゲームの種類です。
 
-pub struct Game;
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Quantum,   // りょうしどうぶつしょうぎ
+    Classical  // どうぶつしょうぎ
+}
 
-impl Game {
-    // コンストラクタです。
+impl Variant {
+    // 初期状態を取得します。
 
-    pub fn initial_state() -> State {
-        State {
-            pieces:     [0b_0_1111, 0b_0_1111, 0b_0_1111, 0b_0_1111, 0b_0_1111, 0b_0_1111, 0b_0_1111, 0b_0_1111],
-            ownership:  0b_0000_1111,
-            bit_boards: [0b_000_000_000_001, 0b_000_000_000_010, 0b_000_000_000_100, 0b_000_000_010_000, 0b_000_010_000_000, 0b_001_000_000_000, 0b_010_000_000_000, 0b_100_000_000_000],
-            turn:       0
+    pub fn initial_state(self) -> State {
+        match self {
+            Variant::Quantum   => Game::initial_state(),
+            Variant::Classical => Game::classical_initial_state()
         }
     }
+}
 
-    // 合法手の集合を取得します。
-
-    pub fn legal_actions(state: &State) -> impl Iterator<Item = (u8, u8)> {
-        // 自分の駒のBitBoardを取得します。
-
-        let ally_bit_board = bits(state.ownership)
-            .filter(|index| state.bit_boards[*index] != 0)
-            .map(|index| state.bit_boards[index])
-            .fold(0, BitOr::bitor);
-
-        // 敵の駒のBitBoardを取得します。
-
-        let enemy_bit_board = bits(!state.ownership)
-            .filter(|index| state.bit_boards[*index] != 0)
-            .map(|index| state.bit_boards[index])
-            .fold(0, BitOr::bitor);
+// ゲームのルールです。
 
-        // 実行可能な駒を指すアクションの集合を取得します。
+pub struct Game;
 
-        let move_piece_actions = bits(state.ownership)
-            .filter(|index| state.bit_boards[*index] != 0)
-            .flat_map(move |index| {
-                let prev_bit = state.bit_boards[index].trailing_zeros() as u8;
-                let next_bits = {
-                    bits(
-                        bits(state.pieces[index])
-                            .map(|piece_bit| NEXTS[piece_bit][prev_bit as usize] & !ally_bit_board)
-                            .fold(0, BitOr::bitor)
-                    ).into_iter().map(|bit| bit as u8)
-                };
+impl Game {
+    // コンストラクタです。
 
-                repeat(prev_bit).zip(next_bits)
-            });
+    pub fn initial_state() -> State {
+        State::new(
+            [0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111],
+            0b_0000_1111,
+            [0b_000_000_000_001, 0b_000_000_000_010, 0b_000_000_000_100, 0b_000_000_010_000, 0b_000_010_000_000, 0b_001_000_000_000, 0b_010_000_000_000, 0b_100_000_000_000],
+            0
+        )
+    }
 
-        // 実行可能な持ち駒を打つアクションの集合を取得します。
+    // どうぶつしょうぎのコンストラクタです。全ての駒が確定しているだけで、ルールはりょうしどうぶつしょうぎと同じです。
 
-        let put_hand_actions = {
-            let number_of_hands = bits(state.ownership)
-                .filter(|index| state.bit_boards[*index] == 0)
-                .count();
+    pub fn classical_initial_state() -> State {
+        State::new(
+            [0b0_0010, 0b0_1000, 0b0_0100, 0b0_0001, 0b0_0001, 0b0_0100, 0b0_1000, 0b0_0010],
+            0b_0000_1111,
+            [0b_000_000_000_001, 0b_000_000_000_010, 0b_000_000_000_100, 0b_000_000_010_000, 0b_000_010_000_000, 0b_001_000_000_000, 0b_010_000_000_000, 0b_100_000_000_000],
+            0
+        )
+    }
 
-            let prev_bits = (4 * 3..4 * 3 + number_of_hands).into_iter().map(|bit| bit as u8);
-            let next_bits = bits(!(ally_bit_board | enemy_bit_board) & 0b_111_111_111_111).map(|bit| bit as u8);  // どうぶつしょうぎには「行き所のない駒」ルールはありません（「ひよこ」を最上段に打てます）。
+    // 合法手の集合を取得します。標準のルール（RuleSet::STANDARD）を使用します。以下の関数も同様です。
 
-            prev_bits.flat_map(move |prev_bit| repeat(prev_bit).zip(next_bits.clone()))
-        };
+    pub fn legal_actions(state: &State) -> impl Iterator<Item = (u8, u8)> {
+        RuleSet::STANDARD.legal_actions(state)
+    }
 
-        // アクションの集合をリターンします。
+    // 同じ結果になる持ち駒を打つアクションを除いた、合法手の集合を取得します。
 
-        move_piece_actions.chain(put_hand_actions)
+    pub fn distinct_legal_actions(state: &State) -> impl Iterator<Item = (u8, u8)> {
+        RuleSet::STANDARD.distinct_legal_actions(state)
     }
 
-    // 「使い切り」による収束（収縮？）を実施します。
+    // 「使い切り」による収束（収縮？）を、ステートを直接変更して実施します。
 
-    fn collapse(state: &State) -> State {
-        let mut result = state.clone();
+    fn collapse(result: &mut State, on_event: &mut impl FnMut(CollapseEvent)) {
 
         // 先手由来と後手由来の2つで収束（収縮？）を実施します（本当は片方だけでよいはずだけど……）。
 
@@ -241,7 +317,11 @@ impl Game {
                     // 他の駒から可能性を削除します。
 
                     for index in removing_indices {
-                        result.pieces[begin_index + index] &= !(target | target << 4);
+                        let before = result.pieces[begin_index + index];
+
+                        result.set_piece(begin_index + index, before & !(target | target << 4));
+
+                        on_event(CollapseEvent::Pigeonhole { index: begin_index + index, target, before, after: result.pieces[begin_index + index] });
                     }
 
                     // 収束（収縮？）で駒の状態が変わったので、外側の無限ループに戻って最初からやり直します。
@@ -254,129 +334,29 @@ impl Game {
                 break;
             }
         }
-
-        result
     }
 
-    // 次のステートを取得します。
-
-    pub fn next_state(state: &State, action: (u8, u8)) -> State {
-        let mut result = state.clone();
-
-        // 次のステートを取得します。
-
-        if action.0 < 4 * 3 {
-            // 駒を指すアクションを実行します。
-
-            (|| {
-                // 移動先に駒があれば、取って持ち駒に加えます。
-
-                if let Some(index) = result.bit_boards.iter().position(|bit_board| bit_board & 1 << action.1 != 0) {
-                    result.pieces[index] = {
-                        let mut result = result.pieces[index];
-
-                        // 成っている駒を元に戻します。
-
-                        result = (result | result >> 4) & 0b_0000_1111;
-
-                        result
-                    };
-                    result.ownership |= 1 << index;
-                    result.bit_boards[index] = 0;
-                }
-
-                // 移動する駒を取得します。
-
-                let index = result.bit_boards.iter().position(|bit_board| bit_board & 1 << action.0 != 0).unwrap();
-
-                // 「絞り込み」による収束（収縮？）を実施します。
-
-                result.pieces[index] = bits(result.pieces[index])
-                    .map(|piece| if NEXTS[piece][action.0 as usize] & 1 << action.1 != 0 { 1 << piece } else { 0 })
-                    .fold(0, BitOr::bitor);
-
-                // 駒の状態が変更されたので、「使い切り」による収束（収縮？）を実施します。
-
-                result = Game::collapse(&result);
-
-                // 駒を移動します。
+    // 次のステートを取得します。アクションが合法手であることをチェックし、合法手でない場合はその理由をエラーとして返します。
 
-                result.pieces[index] = {
-                    let mut result = result.pieces[index];
-
-                    // 敵のエリアに移動したなら、駒を成らせします。
-
-                    if (9..12).contains(&action.1) && result & 0b_0000_0001 != 0 {
-                        result = (result | result << 4) & 0b_0001_1110;  // どうぶつしょうぎで成るのは「ひよこ」だけ。
-                    }
-
-                    result
-                };
-                result.bit_boards[index] = 1 << action.1;
-
-                // 持ち駒から、ライオンの可能性を外します。
-
-                if bits(!result.ownership).filter(|index| result.pieces[*index] & 0b_0000_1000 != 0).count() >= 1 {
-                    for index in bits(result.ownership)
-                        .filter(|index| result.bit_boards[*index] == 0 && result.pieces[*index] & 0b_0000_1000 != 0)
-                        .collect::<ArrayVec<_, 8>>()
-                    {
-                        result.pieces[index] &= !0b_0000_1000;
-                    }
-                }
-
-                // 持ち駒の状態が変更されたので、「使い切り」による収束（収縮？）を実施します。
-
-                result = Game::collapse(&result);
-            })();
-        } else {
-            // 持ち駒を打つアクションを実行します。
-
-            (|| {
-                // 打つ駒を取得します。
-
-                let index = bits(result.ownership)
-                    .filter(|index| result.bit_boards[*index] == 0)
-                    .nth(action.0 as usize - 4 * 3)
-                    .unwrap();
-
-                // 駒を打ちます。
-
-                result.bit_boards[index] = 1 << action.1;
-
-                // どうぶつしょうぎには「行き所のない駒」ルールがないので、持ち駒を打つときには収束（収縮？）は発生しません。
-            })();
-        }
-
-        // 盤面を回転します。
-
-        result.ownership = !result.ownership;
-        result.bit_boards = result.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
-
-        // 手数を進めます。
-
-        result.turn += 1;
-
-        // 次のステートを返します。
-
-        result
+    pub fn try_next_state(state: &State, action: (u8, u8)) -> Result<State, IllegalAction> {
+        RuleSet::STANDARD.try_next_state(state, action)
     }
 
-    // トライに成功して勝ったかを取得します。
+    // 次のステートを取得します。アクションは合法手でなければなりません（合法手でない場合はパニックしたり、不正なステートを返したりします）。
 
-    pub fn won(state: &State) -> bool {
-        bits(state.ownership).any(|index| state.bit_boards[index] & 0b_111_000_000_000 != 0 && state.pieces[index] & 0b_0000_1000 != 0)
+    pub fn next_state(state: &State, action: (u8, u8)) -> State {
+        RuleSet::STANDARD.next_state(state, action)
     }
 
-    // ライオンがキャッチされて負けたかを取得します。
+    // 次のステートと、駒の可能性が減った理由のリストを取得します。
 
-    pub fn lost(state: &State) -> bool {
-        bits(!state.ownership).any(|index| state.bit_boards[index] == 0 && state.pieces[index] & 0b_0000_1000 != 0)
+    pub fn next_state_with_trace(state: &State, action: (u8, u8)) -> (State, Vec<CollapseEvent>) {
+        RuleSet::STANDARD.next_state_with_trace(state, action)
     }
 
-    // 手数が256手になって引き分けになったかを取得します。「千日手」はStateが大きくなってしまうので実装していません。
+    // 手番側から見たゲームの結果を取得します。「千日手」はStateが大きくなってしまうので、GameHistoryで判定します。
 
-    pub fn draw(state: &State) -> bool {
-        state.turn >= MAX_TURN
+    pub fn outcome(state: &State) -> Outcome {
+        RuleSet::STANDARD.outcome(state)
     }
 }
@@ -0,0 +1,61 @@
+use std::{error, fmt};
+
+use crate::{Game, State, bits};
+
+// 仮想的な観測（「もしあの駒がライオンだったら？」）のための機能です。駒の可能性を指定した集合に絞り込み、Game::collapse()と同じ「使い切り」による収束（収縮？）を実施します。
+
+impl Game {
+    // 駒の可能性をpieceとの共通部分に絞り込んだ状態を取得します。1種類に確定させる場合は、pieceに1 << piece_bitを指定してください（「ひよこ」と「にわとり」の両方を残す場合は、0b_1_0001を指定します）。
+
+    pub fn measure(state: &State, index: usize, piece: u8) -> Result<State, MeasurementError> {
+        let narrowed = state.pieces[index] & piece;
+
+        // 駒がその種類である可能性がないなら、エラーにします。
+
+        if narrowed == 0 {
+            return Err(MeasurementError::Impossible { index, piece });
+        }
+
+        let mut result = *state;
+
+        if narrowed != result.pieces[index] {
+            result.set_piece(index, narrowed);
+            Game::collapse(&mut result, &mut |_| ());
+        }
+
+        // 他の駒と矛盾する（可能世界がなくなる）なら、エラーにします。「使い切り」では、矛盾を検出できない場合があります。
+
+        if result.pieces.contains(&0) || result.possible_worlds().next().is_none() {
+            return Err(MeasurementError::Contradiction { index, piece });
+        }
+
+        Ok(result)
+    }
+
+    // 駒の種類ごとに観測した場合の、駒の種類のビットの位置と状態のイテレーターを取得します。矛盾する種類は含みません。
+
+    pub fn measurements(state: &State, index: usize) -> impl Iterator<Item = (usize, State)> + use<> {
+        let state = *state;
+
+        bits(state.pieces[index]).filter_map(move |piece_bit| Game::measure(&state, index, 1 << piece_bit).ok().map(|state| (piece_bit, state)))
+    }
+}
+
+// 観測できなかった場合のエラーです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeasurementError {
+    Impossible { index: usize, piece: u8 },    // 駒がその種類である可能性がない
+    Contradiction { index: usize, piece: u8 }  // 他の駒と矛盾する
+}
+
+impl fmt::Display for MeasurementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeasurementError::Impossible { index, piece }    => write!(f, "cannot measure piece {} as {:#07b}: the piece has no such possibility", index, piece),
+            MeasurementError::Contradiction { index, piece } => write!(f, "cannot measure piece {} as {:#07b}: it contradicts the other pieces", index, piece)
+        }
+    }
+}
+
+impl error::Error for MeasurementError {}
@@ -0,0 +1,185 @@
+use std::{error, fmt, str::FromStr};
+
+use itertools::Itertools;
+
+use crate::{State, bits};
+
+// 局面の文字列表記です。バグ報告やテスト、エンジンへのコマンドに局面を貼り付けられるよう、1行で表します。
+//
+// 例: 初期状態は、"CGEL@c4 CGEL@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0"になります。
+//
+// * 先手由来の駒4つと後手由来の駒4つを、"/"で区切ってState::piecesの順に並べ、最後に手数を書きます。
+// * 駒は、可能性のある動物（C: ひよこ、G: きりん、E: ぞう、L: ライオン、H: にわとり）の後に、"@"とマスを書きます。持ち駒の場合は、マスの代わりに"*"を書きます。
+// * 動物を大文字で書いたら先手の駒、小文字で書いたら後手の駒です。
+// * マスは、先手から見て左からa〜cの列と、上から1〜4の行で表します（どうぶつしょうぎの棋譜と同じです）。
+//
+// Stateは手番側から見た盤面になっていますが、表記は常に先手から見た盤面にします。
+
+const ANIMALS: [char; 5] = ['C', 'G', 'E', 'L', 'H'];
+
+// 文字列表記で表示するためのラッパーです。State::notation()で作成してください。
+
+pub struct Notation<'a>(&'a State);
+
+impl State {
+    // 文字列表記を取得します。
+
+    pub fn notation(&self) -> Notation<'_> {
+        Notation(self)
+    }
+
+    // アクションの文字列表記を取得します。棋譜（GameRecord）と同じく、駒を指すアクションは"b3-b2"、持ち駒を打つアクションは"*0-a2"のように、先手から見たマスで表します。
+
+    pub fn action_notation(&self, action: (u8, u8)) -> String {
+        format_action(action, self.turn)
+    }
+}
+
+// 先手から見たビットの位置に変換します（逆変換も同じ計算になります）。
+
+pub(crate) fn first_bit(bit: u32, turn: u16) -> u32 {
+    if turn.is_multiple_of(2) { bit } else { 4 * 3 - 1 - bit }
+}
+
+// 先手から見たビットの位置を、マスの表記に変換します。
+
+pub(crate) fn format_square(bit: u32) -> String {
+    format!("{}{}", (b'a' + (2 - bit % 3) as u8) as char, 4 - bit / 3)
+}
+
+// アクションを、先手から見たマスの表記に変換します。
+
+pub(crate) fn format_action(action: (u8, u8), turn: u16) -> String {
+    let to = format_square(first_bit(action.1 as u32, turn));
+
+    if action.0 < 4 * 3 {
+        format!("{}-{}", format_square(first_bit(action.0 as u32, turn)), to)
+    } else {
+        format!("*{}-{}", action.0 - 4 * 3, to)
+    }
+}
+
+// マスの表記を、先手から見たビットの位置に変換します。
+
+pub(crate) fn parse_square(square: &str) -> Option<u32> {
+    match square.as_bytes() {
+        [file @ b'a'..=b'c', rank @ b'1'..=b'4'] => Some(((4 - (rank - b'0')) * 3 + (2 - (file - b'a'))) as u32),
+        _                                        => None
+    }
+}
+
+impl fmt::Display for Notation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0;
+        let first_ownership = if state.turn.is_multiple_of(2) { state.ownership } else { !state.ownership };
+
+        let get_piece_string = |index: usize| {
+            let animals = bits(state.pieces[index])
+                .map(|piece_bit| if first_ownership & 1 << index != 0 { ANIMALS[piece_bit] } else { ANIMALS[piece_bit].to_ascii_lowercase() })
+                .collect::<String>();
+
+            let square = if state.bit_boards[index] == 0 {
+                "*".to_string()
+            } else {
+                format_square(first_bit(state.bit_boards[index].trailing_zeros(), state.turn))
+            };
+
+            format!("{}@{}", animals, square)
+        };
+
+        write!(f, "{} / {} {}", (0..4).map(get_piece_string).join(" "), (4..8).map(get_piece_string).join(" "), state.turn)
+    }
+}
+
+// 文字列表記を解析できなかった場合のエラーです。
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseStateError {
+    Format,                   // "/"や手数がない、駒の数が4つではないなど
+    Piece(String),            // 駒を解析できない
+    DuplicateSquare(String),  // 同じマスに複数の駒がある
+    Turn(String)              // 手数を解析できない
+}
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseStateError::Format                  => write!(f, "invalid state notation: expected 4 pieces, \"/\", 4 pieces and a turn"),
+            ParseStateError::Piece(piece)            => write!(f, "invalid state notation: bad piece \"{}\"", piece),
+            ParseStateError::DuplicateSquare(square) => write!(f, "invalid state notation: square \"{}\" is used twice", square),
+            ParseStateError::Turn(turn)              => write!(f, "invalid state notation: bad turn \"{}\"", turn)
+        }
+    }
+}
+
+impl error::Error for ParseStateError {}
+
+impl FromStr for State {
+    type Err = ParseStateError;
+
+    fn from_str(s: &str) -> Result<State, ParseStateError> {
+        // 先手由来の駒と後手由来の駒、手数に分割します。
+
+        let (first_pieces, rest) = s.split_once('/').ok_or(ParseStateError::Format)?;
+        let (second_pieces, turn) = rest.trim().rsplit_once(char::is_whitespace).ok_or(ParseStateError::Format)?;
+
+        let turn = turn.parse::<u16>().map_err(|_| ParseStateError::Turn(turn.to_string()))?;
+
+        let first_tokens = first_pieces.split_whitespace().collect::<Vec<_>>();
+        let second_tokens = second_pieces.split_whitespace().collect::<Vec<_>>();
+
+        if first_tokens.len() != 4 || second_tokens.len() != 4 {
+            return Err(ParseStateError::Format);
+        }
+
+        // 駒を解析します。
+
+        let mut pieces = [0_u8; 8];
+        let mut first_ownership = 0_u8;
+        let mut bit_boards = [0_u16; 8];
+
+        for (index, token) in first_tokens.into_iter().chain(second_tokens).enumerate() {
+            let error = || ParseStateError::Piece(token.to_string());
+
+            let (animals, square) = token.split_once('@').ok_or_else(error)?;
+
+            // 動物を解析します。大文字か小文字かで所有者を判断します。
+
+            if animals.is_empty() || !(animals.chars().all(|c| c.is_ascii_uppercase()) || animals.chars().all(|c| c.is_ascii_lowercase())) {
+                return Err(error());
+            }
+
+            for c in animals.chars() {
+                let piece_bit = ANIMALS.iter().position(|animal| *animal == c.to_ascii_uppercase()).ok_or_else(error)?;
+
+                if pieces[index] & 1 << piece_bit != 0 {
+                    return Err(error());
+                }
+
+                pieces[index] |= 1 << piece_bit;
+            }
+
+            if animals.chars().all(|c| c.is_ascii_uppercase()) {
+                first_ownership |= 1 << index;
+            }
+
+            // マスを解析します。
+
+            if square == "*" {
+                continue;
+            }
+
+            let bit_board = 1 << first_bit(parse_square(square).ok_or_else(error)?, turn);
+
+            if bit_boards.contains(&bit_board) {
+                return Err(ParseStateError::DuplicateSquare(square.to_string()));
+            }
+
+            bit_boards[index] = bit_board;
+        }
+
+        // 手番側から見た状態を作成します。
+
+        Ok(State::new(pieces, if turn.is_multiple_of(2) { first_ownership } else { !first_ownership }, bit_boards, turn))
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+// ゲームの結果です。Stateと同じく、手番側から見た結果になります（Win(Try)なら、手番側のトライが成功しています）。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Ongoing,          // 勝敗が決していない
+    Win(Reason),      // 勝ち
+    Loss(Reason),     // 負け
+    Draw(DrawReason)  // 引き分け
+}
+
+// 勝敗が決した理由です。勝った側と負けた側で、同じ理由を使います。RuleSet::outcome()は手番側から見た結果なので、Win(Try)（手番側のライオンが、前の手でトライして取られなかった）かLoss(Catch)（手番側のライオンが、前の手で取られた）になります。Win(Catch)とLoss(Try)は、Outcome::reversed()でアクションを実行した側から見た結果にした場合に現れます。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Reason {
+    Catch,  // ライオンがキャッチされた（Winなら相手の、Lossなら自分のライオン）
+    Try     // トライに成功した（Winなら自分の、Lossなら相手のライオン）
+}
+
+// 引き分けになった理由です。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DrawReason {
+    TurnLimit,  // 手数がmax_turnになった
+    Repetition  // 千日手になった
+}
+
+impl Outcome {
+    // ゲームが終了しているかを取得します。
+
+    pub fn is_over(&self) -> bool {
+        *self != Outcome::Ongoing
+    }
+
+    // 相手側から見た結果を取得します。Game::next_state()の後の結果は次の手番側から見たものなので、アクションを実行した側の結果を知りたい場合に使用してください。
+
+    pub fn reversed(&self) -> Outcome {
+        match *self {
+            Outcome::Win(reason)  => Outcome::Loss(reason),
+            Outcome::Loss(reason) => Outcome::Win(reason),
+            outcome               => outcome
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Ongoing      => write!(f, "ongoing"),
+            Outcome::Win(reason)  => write!(f, "win by {}", reason),
+            Outcome::Loss(reason) => write!(f, "loss by {}", reason),
+            Outcome::Draw(reason) => write!(f, "draw by {}", reason)
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Catch => write!(f, "catch"),
+            Reason::Try   => write!(f, "try")
+        }
+    }
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawReason::TurnLimit  => write!(f, "turn limit"),
+            DrawReason::Repetition => write!(f, "repetition")
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use arrayvec::ArrayVec;
+
+use crate::{Action, Game, RuleSet, State};
+
+// perft（指定した深さまでの全ての手順を展開して、末端の局面の数を数える）です。合法手の生成や次のステートの取得の、リグレッションを検出するために使用します。
+//
+// 勝敗が決した局面からは展開しないので、深さに達する前に勝敗が決した局面は数えません。
+
+impl RuleSet {
+    // 末端の局面の数を取得します。
+
+    pub fn perft(&self, state: &State, depth: u32) -> u64 {
+        self.perft_in_place(&mut state.clone(), depth)
+    }
+
+    // ルートのアクション（手番側から見たAction）ごとの、末端の局面の数を取得します。
+
+    pub fn perft_divide(&self, state: &State, depth: u32) -> Vec<(Action, u64)> {
+        if depth == 0 || self.outcome(state).is_over() {
+            return Vec::new();
+        }
+
+        self.legal_actions(state)
+            .map(|action| (Action::try_from(action).unwrap(), self.perft(&self.next_state(state, action), depth - 1)))
+            .collect()
+    }
+
+    // make_move()とunmake_move()を使用して、末端の局面の数を取得します。
+
+    fn perft_in_place(&self, state: &mut State, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        if self.outcome(state).is_over() {
+            return 0;
+        }
+
+        let mut result = 0;
+
+        for action in self.legal_actions(state).collect::<ArrayVec<_, 128>>() {
+            let undo = self.make_move(state, action);
+
+            result += self.perft_in_place(state, depth - 1);
+
+            Game::unmake_move(state, undo);
+        }
+
+        result
+    }
+}
+
+impl Game {
+    // 末端の局面の数を取得します。
+
+    pub fn perft(state: &State, depth: u32) -> u64 {
+        RuleSet::STANDARD.perft(state, depth)
+    }
+
+    // ルートのアクションごとの、末端の局面の数を取得します。
+
+    pub fn perft_divide(state: &State, depth: u32) -> Vec<(Action, u64)> {
+        RuleSet::STANDARD.perft_divide(state, depth)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{error, fmt, str::FromStr};
+
+use crate::{GameHistory, IllegalAction, ParseStateError, RuleSet, State, notation::{first_bit, format_action, parse_square}};
+
+// 棋譜です。初期状態と、そこから実行したアクションのリストを保持します。
+//
+// 文字列にすると、以下のようになります。
+//
+// ```text
+// # コメント
+// CGEL@c4 CGEL@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0
+// b3-b2
+// b2-b3
+// *0-a2
+// ```
+//
+// * 1行目（コメントと空行は除きます）は、初期状態の文字列表記（State::notation()）です。
+// * 2行目以降は、1行に1つのアクションです。駒を指すアクションは移動元と移動先のマスを"-"でつなぎ、持ち駒を打つアクションは"*"と持ち駒の順番（Action::Dropのhand_slot）と移動先のマスを"-"でつなぎます。
+// * マスは、局面の文字列表記と同じく先手から見たものです。
+// * "#"から行末まではコメントです。
+
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub initial_state: State,
+    pub actions: Vec<(u8, u8)>
+}
+
+impl GameRecord {
+    // コンストラクタです。
+
+    pub fn new(initial_state: State) -> Self {
+        Self {
+            initial_state,
+            actions: Vec::new()
+        }
+    }
+
+    // 合法手であることと、勝敗が決していないことをチェックしながら棋譜を再生して、最後の局面までの履歴を取得します。
+
+    pub fn replay(&self) -> Result<GameHistory, ReplayError> {
+        self.replay_with_rules(RuleSet::STANDARD)
+    }
+
+    // ルールを指定して、棋譜を再生します。
+
+    pub fn replay_with_rules(&self, rules: RuleSet) -> Result<GameHistory, ReplayError> {
+        self.replay_each(rules, |_| ())
+    }
+
+    // 棋譜を再生して、初期状態から最後の局面までの全ての局面を取得します。
+
+    pub fn states(&self) -> Result<Vec<State>, ReplayError> {
+        let mut result = vec![self.initial_state];
+
+        self.replay_each(RuleSet::STANDARD, |state| result.push(*state))?;
+
+        Ok(result)
+    }
+
+    // 棋譜を再生します。アクションを実行するたびに、次の局面でfを呼び出します。
+
+    fn replay_each(&self, rules: RuleSet, mut f: impl FnMut(&State)) -> Result<GameHistory, ReplayError> {
+        let mut result = GameHistory::with_rules(self.initial_state, rules);
+
+        for (ply, action) in self.actions.iter().enumerate() {
+            if result.outcome().is_over() {
+                return Err(ReplayError::GameOver { ply });
+            }
+
+            result.try_play(*action).map_err(|error| ReplayError::IllegalAction { ply, error })?;
+
+            f(result.state());
+        }
+
+        Ok(result)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.initial_state.notation())?;
+
+        for (ply, action) in self.actions.iter().enumerate() {
+            writeln!(f, "{}", format_action(*action, self.initial_state.turn.wrapping_add(ply as u16)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = ParseRecordError;
+
+    fn from_str(s: &str) -> Result<GameRecord, ParseRecordError> {
+        // コメントと空行を除いた行を取得します。
+
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        // 初期状態を解析します。
+
+        let Some((line_number, line)) = lines.next() else {
+            return Err(ParseRecordError::Empty);
+        };
+
+        let mut result = GameRecord::new(line.parse().map_err(|error| ParseRecordError::State { line_number, error })?);
+
+        // アクションを解析します。
+
+        for (line_number, line) in lines {
+            let error = || ParseRecordError::Action { line_number, action: line.to_string() };
+
+            let turn = result.initial_state.turn.wrapping_add(result.actions.len() as u16);
+
+            let (from, to) = line.split_once('-').ok_or_else(error)?;
+            let to = first_bit(parse_square(to).ok_or_else(error)?, turn) as u8;
+
+            let from = match from.strip_prefix('*') {
+                Some(hand_slot) => 4 * 3 + hand_slot.parse::<u8>().ok().filter(|hand_slot| *hand_slot < 8).ok_or_else(error)?,
+                None            => first_bit(parse_square(from).ok_or_else(error)?, turn) as u8
+            };
+
+            result.actions.push((from, to));
+        }
+
+        Ok(result)
+    }
+}
+
+// 棋譜を解析できなかった場合のエラーです。行番号は1始まりです。
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseRecordError {
+    Empty,                                                 // 初期状態がない
+    State { line_number: usize, error: ParseStateError },  // 初期状態を解析できない
+    Action { line_number: usize, action: String }          // アクションを解析できない
+}
+
+impl fmt::Display for ParseRecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRecordError::Empty                          => write!(f, "invalid game record: no initial state"),
+            ParseRecordError::State { line_number, error }   => write!(f, "invalid game record: line {}: {}", line_number, error),
+            ParseRecordError::Action { line_number, action } => write!(f, "invalid game record: line {}: bad action \"{}\"", line_number, action)
+        }
+    }
+}
+
+impl error::Error for ParseRecordError {}
+
+// 棋譜を再生できなかった場合のエラーです。plyは、何番目（0始まり）のアクションで失敗したかです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    IllegalAction { ply: usize, error: IllegalAction },  // 合法手ではない
+    GameOver { ply: usize }                              // 勝敗が決した後にアクションがある
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::IllegalAction { ply, error } => write!(f, "cannot replay action {}: {}", ply, error),
+            ReplayError::GameOver { ply }             => write!(f, "cannot replay action {}: the game is already over", ply)
+        }
+    }
+}
+
+impl error::Error for ReplayError {}
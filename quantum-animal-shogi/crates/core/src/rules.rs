@@ -0,0 +1,383 @@
+use std::{iter::repeat, ops::BitOr};
+
+use arrayvec::ArrayVec;
+
+use crate::{Action, CollapseEvent, DrawReason, Game, IllegalAction, NEXTS, Outcome, Reason, State, bits, zobrist};
+
+// 敵のエリア（最上段）のBitBoardです。
+
+const LAST_RANK_BIT_BOARD: u16 = 0b_111_000_000_000;
+
+// トライのルールです。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRule {
+    Allowed,   // ライオンが敵のエリアに移動して、取られなければ勝ち（標準）
+    SafeOnly,  // Allowedと同じだけど、敵の駒が利いているマスへのトライは禁止（そのような移動をした駒からは、ライオンの可能性が外れます）
+    Disabled   // トライでは勝ちにならない
+}
+
+// ゲームのルールの設定です。Gameの関数は、標準のルール（RuleSet::STANDARD）を使用します。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleSet {
+    pub max_turn: u16,                  // 引き分けになる手数
+    pub repetition_count: usize,        // 千日手になる同一局面の出現回数（0なら千日手なし。GameHistoryで判定します）
+    pub try_rule: TryRule,              // トライのルール
+    pub promotion: bool,                // 「ひよこ」が敵のエリアに移動したら「にわとり」に成るか
+    pub chick_drop_on_last_rank: bool   // 「ひよこ」を敵のエリアに打てるか（打てない場合は、打った駒から「ひよこ」の可能性が外れます）
+}
+
+impl RuleSet {
+    // 標準のルールです。
+
+    pub const STANDARD: RuleSet = RuleSet {
+        max_turn:                256,
+        repetition_count:        3,
+        try_rule:                TryRule::Allowed,
+        promotion:               true,
+        chick_drop_on_last_rank: true
+    };
+
+    // 敵の駒が利いているマスのBitBoardを取得します。敵の駒の可能性のどれかで移動できるなら、利いているとします。
+
+    fn enemy_attack_bit_board(state: &State) -> u16 {
+        bits(!state.ownership)
+            .filter(|index| state.bit_boards[*index] != 0)
+            .flat_map(|index| {
+                let bit = (state.bit_boards[index].reverse_bits() >> 4).trailing_zeros() as usize;  // 敵から見たビットの位置
+
+                bits(state.pieces[index]).map(move |piece_bit| NEXTS[piece_bit][bit])
+            })
+            .fold(0, BitOr::bitor)
+            .reverse_bits() >> 4
+    }
+
+    // トライが禁止されているマスのBitBoardを取得します。
+
+    fn forbidden_try_bit_board(&self, state: &State) -> u16 {
+        match self.try_rule {
+            TryRule::SafeOnly => LAST_RANK_BIT_BOARD & RuleSet::enemy_attack_bit_board(state),
+            _                 => 0
+        }
+    }
+
+    // 「ひよこ」を打つのが禁止されているマスのBitBoardを取得します。
+
+    fn forbidden_chick_drop_bit_board(&self) -> u16 {
+        if self.chick_drop_on_last_rank { 0 } else { LAST_RANK_BIT_BOARD }
+    }
+
+    // 「絞り込み」後の、移動する駒の可能性を取得します。0なら、移動できません。
+
+    fn moved_piece(piece: u8, prev_bit: u8, next_bit: u8, forbidden_try_bit_board: u16) -> u8 {
+        bits(piece)
+            .filter(|piece_bit| NEXTS[*piece_bit][prev_bit as usize] & 1 << next_bit != 0)
+            .filter(|piece_bit| *piece_bit != 3 || forbidden_try_bit_board & 1 << next_bit == 0)
+            .fold(0, |acc, piece_bit| acc | 1 << piece_bit)
+    }
+
+    // 「絞り込み」後の、打つ駒の可能性を取得します。0なら、打てません。
+
+    fn dropped_piece(&self, piece: u8, next_bit: u8) -> u8 {
+        if self.forbidden_chick_drop_bit_board() & 1 << next_bit != 0 { piece & !0b_0000_0001 } else { piece }
+    }
+
+    // 合法手の集合を取得します。
+
+    pub fn legal_actions<'a>(&self, state: &'a State) -> impl Iterator<Item = (u8, u8)> + use<'a> {
+        // 自分の駒のBitBoardを取得します。
+
+        let ally_bit_board = bits(state.ownership)
+            .filter(|index| state.bit_boards[*index] != 0)
+            .map(|index| state.bit_boards[index])
+            .fold(0, BitOr::bitor);
+
+        // 敵の駒のBitBoardを取得します。
+
+        let enemy_bit_board = bits(!state.ownership)
+            .filter(|index| state.bit_boards[*index] != 0)
+            .map(|index| state.bit_boards[index])
+            .fold(0, BitOr::bitor);
+
+        // ルールで禁止されているマスのBitBoardを取得します。
+
+        let forbidden_try_bit_board = self.forbidden_try_bit_board(state);
+        let forbidden_chick_drop_bit_board = self.forbidden_chick_drop_bit_board();
+
+        // 実行可能な駒を指すアクションの集合を取得します。
+
+        let move_piece_actions = bits(state.ownership)
+            .filter(|index| state.bit_boards[*index] != 0)
+            .flat_map(move |index| {
+                let prev_bit = state.bit_boards[index].trailing_zeros() as u8;
+                let next_bits = {
+                    bits(
+                        bits(state.pieces[index])
+                            .map(|piece_bit| NEXTS[piece_bit][prev_bit as usize] & !ally_bit_board & if piece_bit == 3 { !forbidden_try_bit_board } else { !0 })
+                            .fold(0, BitOr::bitor)
+                    ).map(|bit| bit as u8)
+                };
+
+                repeat(prev_bit).zip(next_bits)
+            });
+
+        // 実行可能な持ち駒を打つアクションの集合を取得します。
+
+        let put_hand_actions = bits(state.ownership)
+            .filter(|index| state.bit_boards[*index] == 0)
+            .enumerate()
+            .flat_map(move |(hand_slot, index)| {
+                let prev_bit = (4 * 3 + hand_slot) as u8;
+                let next_bits = {
+                    bits(!(ally_bit_board | enemy_bit_board) & 0b_111_111_111_111 & if state.pieces[index] == 0b_0000_0001 { !forbidden_chick_drop_bit_board } else { !0 })  // 標準のルールでは、「ひよこ」を最上段に打てます（どうぶつしょうぎには「行き所のない駒」ルールはありません）。
+                        .map(|bit| bit as u8)
+                };
+
+                repeat(prev_bit).zip(next_bits)
+            });
+
+        // アクションの集合をリターンします。
+
+        move_piece_actions.chain(put_hand_actions)
+    }
+
+    // 同じ結果になる持ち駒を打つアクションを除いた、合法手の集合を取得します。除いたアクションは、State::representative_action()で残したアクションに変換できます。探索の分岐数を減らしたい場合に使用してください。
+
+    pub fn distinct_legal_actions<'a>(&self, state: &'a State) -> impl Iterator<Item = (u8, u8)> + use<'a> {
+        self.legal_actions(state).filter(|action| state.representative_action(*action) == Some(*action))
+    }
+
+    // 次のステートを取得します。アクションが合法手であることをチェックし、合法手でない場合はその理由をエラーとして返します。
+
+    pub fn try_next_state(&self, state: &State, action: (u8, u8)) -> Result<State, IllegalAction> {
+        let ally_bit_board = bits(state.ownership).map(|index| state.bit_boards[index]).fold(0, BitOr::bitor);
+        let all_bit_board = state.bit_boards.iter().fold(0, BitOr::bitor);
+
+        match Action::try_from(action)? {
+            Action::Move { from, to } => {
+                // 移動元の駒を取得します。
+
+                let Some(index) = state.bit_boards.iter().position(|bit_board| bit_board & from.bit_board() != 0) else {
+                    return Err(IllegalAction::EmptySquare(from));
+                };
+
+                if state.ownership & 1 << index == 0 {
+                    return Err(IllegalAction::OpponentPiece(from));
+                }
+
+                // 移動先に自分の駒がないことをチェックします。
+
+                if ally_bit_board & to.bit_board() != 0 {
+                    return Err(IllegalAction::OccupiedSquare(to));
+                }
+
+                // 駒の可能性のどれかで移動できることをチェックします。移動できない場合に実行すると、「絞り込み」で駒の可能性が空になってしまいます。
+
+                if RuleSet::moved_piece(state.pieces[index], from.bit(), to.bit(), self.forbidden_try_bit_board(state)) == 0 {
+                    return Err(IllegalAction::Unreachable { from, to });
+                }
+            }
+            Action::Drop { hand_slot, to } => {
+                // 持ち駒があることをチェックします。
+
+                let Some(index) = bits(state.ownership).filter(|index| state.bit_boards[*index] == 0).nth(hand_slot as usize) else {
+                    return Err(IllegalAction::BadHandSlot(hand_slot));
+                };
+
+                // 打つ先に駒がないことをチェックします。
+
+                if all_bit_board & to.bit_board() != 0 {
+                    return Err(IllegalAction::OccupiedSquare(to));
+                }
+
+                // ルールで禁止されていないことをチェックします。
+
+                if self.dropped_piece(state.pieces[index], to.bit()) == 0 {
+                    return Err(IllegalAction::ForbiddenDrop { hand_slot, to });
+                }
+            }
+        }
+
+        Ok(self.next_state(state, action))
+    }
+
+    // 次のステートを取得します。アクションは合法手でなければなりません（合法手でない場合はパニックしたり、不正なステートを返したりします）。
+
+    pub fn next_state(&self, state: &State, action: (u8, u8)) -> State {
+        let mut result = *state;
+
+        self.apply(&mut result, action, &mut |_| ());
+
+        result
+    }
+
+    // 次のステートと、駒の可能性が減った理由のリストを取得します。アクションは合法手でなければなりません。
+
+    pub fn next_state_with_trace(&self, state: &State, action: (u8, u8)) -> (State, Vec<CollapseEvent>) {
+        let mut result = *state;
+        let mut events = Vec::new();
+
+        self.apply(&mut result, action, &mut |event| events.push(event));
+
+        (result, events)
+    }
+
+    // アクションを実行して、ステートを直接次のステートにします。駒の可能性が減るたびに、on_eventを呼び出します。
+
+    pub(crate) fn apply(&self, result: &mut State, action: (u8, u8), on_event: &mut impl FnMut(CollapseEvent)) {
+        // 次のステートにします。
+
+        if action.0 < 4 * 3 {
+            // 駒を指すアクションを実行します。
+
+            (|| {
+                // 全ての駒が確定している（どうぶつしょうぎの状態になっている）なら、駒の可能性は変化しないので、収束（収縮？）を省略します。
+
+                let classical = result.is_classical();
+
+                // トライが禁止されているマスを取得します。駒を取る前に取得しておきます。
+
+                let forbidden_try_bit_board = if classical { 0 } else { self.forbidden_try_bit_board(result) };
+
+                // 移動先に駒があれば、取って持ち駒に加えます。
+
+                if let Some(index) = result.bit_boards.iter().position(|bit_board| bit_board & 1 << action.1 != 0) {
+                    result.set_piece(index, {
+                        let mut result = result.pieces[index];
+
+                        // 成っている駒を元に戻します。
+
+                        result = (result | result >> 4) & 0b_0000_1111;
+
+                        result
+                    });
+                    result.toggle_ownership(index);
+                    result.set_bit_board(index, 0);
+                }
+
+                // 移動する駒を取得します。
+
+                let index = result.bit_boards.iter().position(|bit_board| bit_board & 1 << action.0 != 0).unwrap();
+
+                if !classical {
+                    // 「絞り込み」による収束（収縮？）を実施します。
+
+                    let before = result.pieces[index];
+
+                    result.set_piece(index, RuleSet::moved_piece(before, action.0, action.1, forbidden_try_bit_board));
+
+                    if result.pieces[index] != before {
+                        on_event(CollapseEvent::Narrowed { index, before, after: result.pieces[index] });
+                    }
+
+                    // 駒の状態が変更されたので、「使い切り」による収束（収縮？）を実施します。
+
+                    Game::collapse(result, on_event);
+                }
+
+                // 駒を移動します。
+
+                result.set_piece(index, {
+                    let mut result = result.pieces[index];
+
+                    // 敵のエリアに移動したなら、駒を成らせします。
+
+                    if self.promotion && LAST_RANK_BIT_BOARD & 1 << action.1 != 0 && result & 0b_0000_0001 != 0 {
+                        result = (result | result << 4) & 0b_0001_1110;  // どうぶつしょうぎで成るのは「ひよこ」だけ。
+                    }
+
+                    result
+                });
+                result.set_bit_board(index, 1 << action.1);
+
+                if classical {
+                    return;
+                }
+
+                // 持ち駒から、ライオンの可能性を外します。
+
+                if bits(!result.ownership).filter(|index| result.pieces[*index] & 0b_0000_1000 != 0).count() >= 1 {
+                    for index in bits(result.ownership)
+                        .filter(|index| result.bit_boards[*index] == 0 && result.pieces[*index] & 0b_0000_1000 != 0)
+                        .collect::<ArrayVec<_, 8>>()
+                    {
+                        let before = result.pieces[index];
+
+                        result.set_piece(index, before & !0b_0000_1000);
+
+                        on_event(CollapseEvent::LionRemovedFromHand { index, before, after: result.pieces[index] });
+                    }
+                }
+
+                // 持ち駒の状態が変更されたので、「使い切り」による収束（収縮？）を実施します。
+
+                Game::collapse(result, on_event);
+            })();
+        } else {
+            // 持ち駒を打つアクションを実行します。
+
+            // 打つ駒を取得します。
+
+            let index = bits(result.ownership)
+                .filter(|index| result.bit_boards[*index] == 0)
+                .nth(action.0 as usize - 4 * 3)
+                .unwrap();
+
+            // 駒を打ちます。
+
+            result.set_bit_board(index, 1 << action.1);
+
+            // 標準のルールには「行き所のない駒」ルールがないので、持ち駒を打つときには収束（収縮？）は発生しません。「ひよこ」を敵のエリアに打てないルールの場合は、「絞り込み」と「使い切り」による収束（収縮？）を実施します。
+
+            let piece = self.dropped_piece(result.pieces[index], action.1);
+
+            if piece != result.pieces[index] {
+                on_event(CollapseEvent::Narrowed { index, before: result.pieces[index], after: piece });
+
+                result.set_piece(index, piece);
+                Game::collapse(result, on_event);
+            }
+        }
+
+        // 盤面を回転します。ハッシュ値は先手から見た座標で計算しているので、手番だけを入れ替えます。
+
+        result.hash ^= zobrist::turn_key();
+        result.ownership = !result.ownership;
+        result.bit_boards = result.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
+
+        // 手数を進めます。
+
+        result.turn += 1;
+    }
+
+    // 手番側から見たゲームの結果を取得します。千日手はStateだけでは判定できないので、GameHistory::outcome()を使用してください。
+
+    pub fn outcome(&self, state: &State) -> Outcome {
+        // トライに成功して勝ったかを判定します。
+
+        if self.try_rule != TryRule::Disabled && bits(state.ownership).any(|index| state.bit_boards[index] & LAST_RANK_BIT_BOARD != 0 && state.pieces[index] & 0b_0000_1000 != 0) {
+            return Outcome::Win(Reason::Try);
+        }
+
+        // ライオンがキャッチされて負けたかを判定します。
+
+        if bits(!state.ownership).any(|index| state.bit_boards[index] == 0 && state.pieces[index] & 0b_0000_1000 != 0) {
+            return Outcome::Loss(Reason::Catch);
+        }
+
+        // 手数がmax_turnになって引き分けになったかを判定します。
+
+        if state.turn >= self.max_turn {
+            return Outcome::Draw(DrawReason::TurnLimit);
+        }
+
+        Outcome::Ongoing
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::STANDARD
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{Action, GameHistory, Square, State};
+
+// 盤面の左右反転です。どうぶつしょうぎのルールは左右対称なので、左右反転した局面は同じ価値を持ちます。学習データの水増しや、探索での同一局面の判定に使用してください。
+//
+// 持ち駒は盤面の外なので、左右反転しても変わりません（持ち駒を打つアクションのhand_slotも変わりません）。
+
+impl Square {
+    // 左右反転した場合のマスを取得します。
+
+    pub fn mirrored(self) -> Square {
+        Square::from_rank_file(self.rank(), 3 - 1 - self.file()).unwrap()
+    }
+}
+
+impl Action {
+    // 左右反転した場合のアクションを取得します。
+
+    pub fn mirrored(self) -> Action {
+        match self {
+            Action::Move { from, to }      => Action::Move { from: from.mirrored(), to: to.mirrored() },
+            Action::Drop { hand_slot, to } => Action::Drop { hand_slot, to: to.mirrored() }
+        }
+    }
+
+    // 左右反転した場合の、Python側のインデックスを取得します。Python側の合法手のマスクやポリシーを左右反転する場合は、mirrored[i] = original[Action::mirrored_index(i)]で変換してください（左右反転は2回実行すると元に戻るので、どちら向きの変換でも同じです）。
+
+    pub fn mirrored_index(index: usize) -> Option<usize> {
+        Action::from_index(index).map(|action| action.mirrored().index())
+    }
+}
+
+impl State {
+    // 左右反転した状態を取得します。
+
+    pub fn mirrored(&self) -> State {
+        let mut result = *self;
+
+        result.bit_boards = self.bit_boards.map(mirror_bit_board);
+        result.hash = result.compute_hash();
+
+        result
+    }
+}
+
+impl GameHistory {
+    // 左右反転した履歴を取得します。千日手を判定できるよう、棋譜の全ての局面を左右反転します。
+
+    pub fn mirrored(&self) -> GameHistory {
+        let mut result = GameHistory::with_rules(self.record().initial_state.mirrored(), *self.rules());
+
+        for action in &self.record().actions {
+            result.play(Action::try_from(*action).unwrap().mirrored().into());
+        }
+
+        result
+    }
+}
+
+// BitBoardを左右反転します。
+
+fn mirror_bit_board(bit_board: u16) -> u16 {
+    (0..4).fold(0, |acc, rank| {
+        let row = bit_board >> (rank * 3) & 0b_111;
+
+        acc | ((row & 0b_001) << 2 | row & 0b_010 | (row & 0b_100) >> 2) << (rank * 3)
+    })
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::bits;
+
+// 駒の可能性が減った理由（収束（収縮？）のイベント）です。indexはState::piecesのインデックスで、beforeとafterは変化前と変化後の駒の可能性です。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollapseEvent {
+    Narrowed { index: usize, before: u8, after: u8 },               // 「絞り込み」（その動きができない種類や、ルールで禁止された種類を外した）
+    LionRemovedFromHand { index: usize, before: u8, after: u8 },    // 敵の駒にライオンの可能性が残っているので、持ち駒からライオンの可能性を外した
+    Pigeonhole { index: usize, target: u8, before: u8, after: u8 }  // 「使い切り」（targetの種類は、他の駒で使い切られている）
+}
+
+impl CollapseEvent {
+    // 駒のインデックスを取得します。
+
+    pub fn index(&self) -> usize {
+        match self {
+            CollapseEvent::Narrowed { index, .. }            => *index,
+            CollapseEvent::LionRemovedFromHand { index, .. } => *index,
+            CollapseEvent::Pigeonhole { index, .. }          => *index
+        }
+    }
+}
+
+// 駒の可能性の集合を文字列化します。
+
+fn piece_set_string(piece: u8) -> String {
+    format!("{{{}}}", bits(piece).map(|piece_bit| ["chick", "giraffe", "elephant", "lion", "hen"][piece_bit]).join(", "))
+}
+
+impl fmt::Display for CollapseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollapseEvent::Narrowed { index, after, .. }            => write!(f, "piece {} narrowed to {} by the action", index, piece_set_string(*after)),
+            CollapseEvent::LionRemovedFromHand { index, after, .. } => write!(f, "lion removed from hand piece {} because an enemy lion candidate remains, leaving {}", index, piece_set_string(*after)),
+            CollapseEvent::Pigeonhole { index, target, after, .. }  => write!(f, "pigeonhole collapse on set {} narrowed piece {} to {}", piece_set_string(*target), index, piece_set_string(*after))
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{Game, RuleSet, State, bits};
+
+// アクションを取り消すための記録です。RuleSet::make_move()（Game::make_move()）で作成し、Game::unmake_move()に渡してください。
+//
+// 収束（収縮？）で駒の可能性がどのように変わったかを追跡するのは大変なので、実行前の駒の可能性をそのまま保持します。
+
+#[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    pieces: [u8; 8],             // 実行前の駒の可能性
+    ownership: u8,               // 実行前の駒の所有者
+    hash: u64,                   // 実行前のハッシュ値
+    moved: (u8, u16),            // 移動した（打った）駒のインデックスと、実行前の駒の位置
+    captured: Option<(u8, u16)>  // 取った駒のインデックスと、実行前の駒の位置
+}
+
+impl RuleSet {
+    // アクションを実行して、ステートを直接次のステートにします。アクションは合法手でなければなりません。探索でステートのコピーを避けたい場合に使用してください。
+
+    pub fn make_move(&self, state: &mut State, action: (u8, u8)) -> Undo {
+        let moved = if action.0 < 4 * 3 {
+            state.bit_boards.iter().position(|bit_board| bit_board & 1 << action.0 != 0).unwrap()
+        } else {
+            bits(state.ownership).filter(|index| state.bit_boards[*index] == 0).nth(action.0 as usize - 4 * 3).unwrap()
+        };
+
+        let captured = state.bit_boards.iter().position(|bit_board| bit_board & 1 << action.1 != 0);
+
+        let result = Undo {
+            pieces: state.pieces,
+            ownership: state.ownership,
+            hash: state.hash,
+            moved: (moved as u8, state.bit_boards[moved]),
+            captured: captured.map(|index| (index as u8, state.bit_boards[index]))
+        };
+
+        self.apply(state, action, &mut |_| ());
+
+        result
+    }
+}
+
+impl Game {
+    // アクションを実行して、ステートを直接次のステートにします。
+
+    pub fn make_move(state: &mut State, action: (u8, u8)) -> Undo {
+        RuleSet::STANDARD.make_move(state, action)
+    }
+
+    // make_move()で実行したアクションを取り消して、ステートを直接前のステートに戻します。make_move()とは逆の順序で呼び出してください。ルールには依存しません。
+
+    pub fn unmake_move(state: &mut State, undo: Undo) {
+        // 手数を戻します。
+
+        state.turn -= 1;
+
+        // 盤面を回転します（2回回転すると元に戻ります）。
+
+        state.bit_boards = state.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
+
+        // 駒の位置を戻します。
+
+        state.bit_boards[undo.moved.0 as usize] = undo.moved.1;
+
+        if let Some((index, bit_board)) = undo.captured {
+            state.bit_boards[index as usize] = bit_board;
+        }
+
+        // 駒の可能性と所有者、ハッシュ値を戻します。
+
+        state.pieces = undo.pieces;
+        state.ownership = undo.ownership;
+        state.hash = undo.hash;
+    }
+}
@@ -0,0 +1,97 @@
+use arrayvec::ArrayVec;
+use itertools::{Itertools, iproduct};
+
+use crate::State;
+
+// 駒の可能性の組み合わせ（可能世界）を取得するための機能です。
+//
+// 可能世界では、全ての駒が確定しています（piecesの各要素で、立っているビットが1つだけになります）。先手由来の4駒と後手由来の4駒は、それぞれ「ひよこ」（成っている場合は「にわとり」）、「きりん」、「ぞう」、「ライオン」を1つずつ持ちます。
+
+// 駒の種類ごとの、可能世界の数です。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Marginals {
+    pub world_count: usize,      // 可能世界の数
+    pub counts: [[usize; 5]; 8]  // [駒、駒の種類]ごとの、その駒がその種類である可能世界の数
+}
+
+impl Marginals {
+    // 駒がその種類である確率（全ての可能世界が同じ確率だとした場合）を取得します。
+
+    pub fn probability(&self, index: usize, piece_bit: usize) -> f64 {
+        if self.world_count == 0 {
+            return 0.0;
+        }
+
+        self.counts[index][piece_bit] as f64 / self.world_count as f64
+    }
+}
+
+// 由来が同じ4駒の、可能世界の集合を取得します。
+
+fn group_worlds(pieces: &[u8]) -> ArrayVec<[u8; 4], 24> {
+    (0..4)
+        .permutations(4)
+        .filter_map(|animals| {
+            animals
+                .into_iter()
+                .zip(pieces)
+                .map(|(animal, piece)| {
+                    // 「ひよこ」は、成っている場合は「にわとり」になります。
+
+                    let candidates = if animal == 0 { 0b1_0001 } else { 1 << animal };
+
+                    match piece & candidates {
+                        0     => None,
+                        piece => Some(piece)
+                    }
+                })
+                .collect::<Option<ArrayVec<_, 4>>>()
+                .map(|world| world.into_inner().unwrap())
+        })
+        .collect()
+}
+
+impl State {
+    // 可能世界のイテレーターを取得します。可能世界ごとに、確定した駒の配列を返します。
+
+    pub fn possible_worlds(&self) -> impl Iterator<Item = [u8; 8]> + use<> {
+        iproduct!(group_worlds(&self.pieces[0..4]), group_worlds(&self.pieces[4..8]))
+            .map(|(first_world, second_world)| {
+                let mut result = [0; 8];
+
+                result[0..4].copy_from_slice(&first_world);
+                result[4..8].copy_from_slice(&second_world);
+
+                result
+            })
+    }
+
+    // 駒の種類ごとの、可能世界の数を取得します。
+
+    pub fn marginals(&self) -> Marginals {
+        let mut result = Marginals { world_count: 0, counts: [[0; 5]; 8] };
+
+        for world in self.possible_worlds() {
+            result.world_count += 1;
+
+            for (index, piece) in world.into_iter().enumerate() {
+                result.counts[index][piece.trailing_zeros() as usize] += 1;
+            }
+        }
+
+        result
+    }
+
+    // 可能世界のどれかで取りうる駒の種類を取得します。「使い切り」による収束（収縮？）が正しければ、piecesと同じになります。
+
+    pub fn feasible_pieces(&self) -> [u8; 8] {
+        self.possible_worlds().fold([0; 8], |acc, world| [0, 1, 2, 3, 4, 5, 6, 7].map(|index| acc[index] | world[index]))
+    }
+
+    // 可能世界の状態（全ての駒が確定した状態）を取得します。
+
+    pub fn to_classical(&self, world: [u8; 8]) -> State {
+        State::new(world, self.ownership, self.bit_boards, self.turn)
+    }
+}
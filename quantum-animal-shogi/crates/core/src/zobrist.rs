@@ -0,0 +1,78 @@
+use std::sync::LazyLock;
+
+use crate::{State, bits};
+
+// Zobristハッシュ用の乱数表です。手番のたびに盤面を回転してもハッシュ値を差分で更新できるよう、マスの位置と所有者は先手から見たものにします。
+
+struct Keys {
+    pieces:     [[u64; 1 << 5]; 8],   // [駒、駒の可能性]
+    ownership:  [u64; 8],             // [駒]（先手が所有している場合）
+    bit_boards: [[u64; 4 * 3]; 8],    // [駒、先手から見たビットの位置]（持ち駒の場合はなし）
+    turn:       u64                   // 後手番
+}
+
+static KEYS: LazyLock<Keys> = LazyLock::new(|| {
+    // 実行のたびにハッシュ値が変わると困るので、シードを固定したSplitMix64で乱数を生成します。
+
+    let mut seed = 0x_9e37_79b9_7f4a_7c15_u64;
+
+    let mut next = || {
+        seed = seed.wrapping_add(0x_9e37_79b9_7f4a_7c15);
+
+        let mut result = seed;
+
+        result = (result ^ result >> 30).wrapping_mul(0x_bf58_476d_1ce4_e5b9);
+        result = (result ^ result >> 27).wrapping_mul(0x_94d0_49bb_1331_11eb);
+
+        result ^ result >> 31
+    };
+
+    Keys {
+        pieces:     [(); 8].map(|_| [(); 1 << 5].map(|_| next())),
+        ownership:  [(); 8].map(|_| next()),
+        bit_boards: [(); 8].map(|_| [(); 4 * 3].map(|_| next())),
+        turn:       next()
+    }
+});
+
+// 駒の可能性のキーを取得します。
+
+pub(crate) fn piece_key(index: usize, piece: u8) -> u64 {
+    KEYS.pieces[index][piece as usize]
+}
+
+// 所有者のキーを取得します。所有者が入れ替わったら、このキーでXORしてください。
+
+pub(crate) fn ownership_key(index: usize) -> u64 {
+    KEYS.ownership[index]
+}
+
+// 駒の位置のキーを取得します。
+
+pub(crate) fn bit_board_key(index: usize, bit_board: u16, turn: u16) -> u64 {
+    if bit_board == 0 {
+        return 0;
+    }
+
+    let bit = bit_board.trailing_zeros() as usize;
+
+    KEYS.bit_boards[index][if turn.is_multiple_of(2) { bit } else { 4 * 3 - 1 - bit }]
+}
+
+// 手番のキーを取得します。手番が入れ替わったら、このキーでXORしてください。
+
+pub(crate) fn turn_key() -> u64 {
+    KEYS.turn
+}
+
+// ハッシュ値を最初から計算します。
+
+pub(crate) fn hash(state: &State) -> u64 {
+    let first_ownership = if state.turn.is_multiple_of(2) { state.ownership } else { !state.ownership };
+
+    (0..8)
+        .map(|index| piece_key(index, state.pieces[index]) ^ bit_board_key(index, state.bit_boards[index], state.turn))
+        .chain(bits(first_ownership).map(ownership_key))
+        .chain((!state.turn.is_multiple_of(2)).then(turn_key))
+        .fold(0, |acc, key| acc ^ key)
+}
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use quantum_animal_shogi_core::{Action, InvalidAction, Square};
+
+fn square(bit: u8) -> Square {
+    Square::new(bit).unwrap()
+}
+
+// 全てのPython側のインデックスが、アクションとタプルを経由して元に戻ることを確認します。
+
+#[test]
+fn all_indices_round_trip() {
+    let mut tuples = HashSet::new();
+
+    for index in 0..Action::INDEX_COUNT {
+        let action = Action::from_index(index).unwrap();
+
+        assert_eq!(action.index(), index);
+
+        let tuple = <(u8, u8)>::from(action);
+
+        assert_eq!(Action::try_from(tuple), Ok(action));
+
+        tuples.insert(tuple);
+    }
+
+    // 異なるインデックスは、異なるタプルになります。
+
+    assert_eq!(tuples.len(), Action::INDEX_COUNT);
+
+    assert_eq!(Action::from_index(Action::INDEX_COUNT), None);
+    assert_eq!(Action::from_index(usize::MAX), None);
+}
+
+// Python側のインデックスは、盤面の左上を0にした座標系になることを確認します。
+
+#[test]
+fn indices_use_the_python_coordinates() {
+    // 先手のb3（4）からb2（7）は、Python側では7 * 12 + 4です。
+
+    assert_eq!(Action::from_index(7 * 12 + 4), Some(Action::Move { from: square(4), to: square(7) }));
+    assert_eq!(Action::Move { from: square(4), to: square(7) }.index(), 7 * 12 + 4);
+
+    // 2番目の持ち駒をa3（5）に打つのは、(12 + 2) * 12 + 6です。
+
+    assert_eq!(Action::from_index((12 + 2) * 12 + 6), Some(Action::Drop { hand_slot: 2, to: square(5) }));
+    assert_eq!(<(u8, u8)>::from(Action::Drop { hand_slot: 2, to: square(5) }), (4 * 3 + 2, 5));
+}
+
+// アクションとして解釈できないタプルが、エラーになることを確認します。
+
+#[test]
+fn invalid_tuples_are_rejected() {
+    for tuple in [(0, 4 * 3), (11, u8::MAX), (4 * 3, 4 * 3), (4 * 3 + 8, 0), (u8::MAX, 0)] {
+        assert_eq!(Action::try_from(tuple), Err(InvalidAction(tuple)));
+    }
+
+    assert_eq!(Action::try_from((11, 0)), Ok(Action::Move { from: square(11), to: square(0) }));
+    assert_eq!(Action::try_from((4 * 3 + 7, 11)), Ok(Action::Drop { hand_slot: 7, to: square(11) }));
+}
@@ -0,0 +1,41 @@
+// プロパティ・テストで共有する、ランダムな対局の生成です。テストのファイルごとにクレートになるので、使わない関数があってもエラーにしません。
+
+#![allow(dead_code)]
+
+use proptest::prelude::*;
+use quantum_animal_shogi_core::{RuleSet, State, TryRule, Variant};
+
+pub fn variant() -> impl Strategy<Value = Variant> {
+    prop_oneof![Just(Variant::Quantum), Just(Variant::Classical)]
+}
+
+pub fn rule_set() -> impl Strategy<Value = RuleSet> {
+    (prop_oneof![Just(TryRule::Allowed), Just(TryRule::SafeOnly), Just(TryRule::Disabled)], any::<bool>(), any::<bool>())
+        .prop_map(|(try_rule, promotion, chick_drop_on_last_rank)| RuleSet { try_rule, promotion, chick_drop_on_last_rank, ..RuleSet::STANDARD })
+}
+
+// choicesで合法手を選びながら対局して、各局面でfを呼び出します。
+
+pub fn play(rules: &RuleSet, state: State, choices: &[usize], mut f: impl FnMut(&State, (u8, u8)) -> Result<(), TestCaseError>) -> Result<(), TestCaseError> {
+    let mut state = state;
+
+    for choice in choices {
+        if rules.outcome(&state).is_over() {
+            break;
+        }
+
+        let actions = rules.legal_actions(&state).collect::<Vec<_>>();
+
+        if actions.is_empty() {
+            break;
+        }
+
+        let action = actions[choice % actions.len()];
+
+        f(&state, action)?;
+
+        state = rules.next_state(&state, action);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{play, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, RuleSet, State};
+
+// 先手が、後手由来の「ひよこ」か「ぞう」の駒を2つ持ち駒にしている局面です。空いているマスは、c3、a3、c2、b2、a2、c1の6つです。
+
+const TWIN_HAND_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / CE@* CE@* gl@b1 gl@a1 0";
+
+// 由来と駒の可能性が同じ持ち駒は、最初の持ち駒を打つアクションだけが代表になることを確認します。
+
+#[test]
+fn twin_hand_pieces_have_one_representative() {
+    let state = TWIN_HAND_STATE.parse::<State>().unwrap();
+
+    let drops = Game::legal_actions(&state).filter(|action| action.0 >= 4 * 3).collect::<Vec<_>>();
+    let distinct_drops = Game::distinct_legal_actions(&state).filter(|action| action.0 >= 4 * 3).collect::<Vec<_>>();
+
+    assert_eq!(drops.len(), 2 * 6);
+    assert_eq!(distinct_drops.len(), 6);
+    assert!(distinct_drops.iter().all(|action| action.0 == 4 * 3));
+
+    // 2番目の持ち駒を打つアクションは、最初の持ち駒を打つアクションになります。駒を指すアクションは、そのままです。
+
+    assert_eq!(state.representative_action((4 * 3 + 1, 3)), Some((4 * 3, 3)));
+    assert_eq!(state.representative_action((4 * 3, 3)), Some((4 * 3, 3)));
+    assert_eq!(state.representative_action((4, 7)), Some((4, 7)));
+}
+
+// 持ち駒がない場合は、Noneになることを確認します。
+
+#[test]
+fn representative_action_rejects_missing_hand_slots() {
+    let state = Game::initial_state();
+
+    assert_eq!(state.representative_action((11, 8)), Some((11, 8)));
+    assert_eq!(state.representative_action((4 * 3, 6)), None);
+    assert_eq!(state.representative_action((u8::MAX, 6)), None);
+
+    assert_eq!(TWIN_HAND_STATE.parse::<State>().unwrap().representative_action((4 * 3 + 2, 3)), None);
+}
+
+proptest! {
+    // ランダムな対局で、代表のアクションが重複を除いた合法手に含まれて、元のアクションと同じ結果になることを確認します。
+
+    #[test]
+    fn representative_actions_are_equivalent(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, _| {
+            let distinct_actions = Game::distinct_legal_actions(state).collect::<Vec<_>>();
+
+            for action in Game::legal_actions(state) {
+                let representative_action = state.representative_action(action).unwrap();
+
+                prop_assert!(distinct_actions.contains(&representative_action));
+
+                // 駒のインデックスが入れ替わっただけなので、由来ごとに駒をソートすれば同じになります。
+
+                let sorted_pieces = |state: State| {
+                    let mut result = (0..8).map(|index| (index / 4, state.pieces[index], state.ownership & 1 << index != 0, state.bit_boards[index])).collect::<Vec<_>>();
+
+                    result.sort();
+                    result
+                };
+
+                prop_assert_eq!(sorted_pieces(Game::next_state(state, action)), sorted_pieces(Game::next_state(state, representative_action)));
+            }
+
+            Ok(())
+        })?;
+    }
+}
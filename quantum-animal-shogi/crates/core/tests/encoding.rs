@@ -0,0 +1,153 @@
+mod common;
+
+use common::{play, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, ObservationLayout, RuleSet, State};
+
+// 後手の手番で、後手が先手由来の「ひよこ」（インデックス3、駒の番号7）を持ち駒にしている局面です。
+
+const HAND_STATE: &str = "G@c4 L@b4 E@a4 c@* / C@a3 e@b2 l@b1 g@a1 3";
+
+// 最初のバージョンのobservation()（Python向けの実装）と同じ変換です。"rows-v1"は、これと同じでなければなりません。
+
+fn first_observation(state: &State) -> Vec<f32> {
+    let mut result = [[0.0_f32; 5 + 2 + 2]; 4 * 3 + 8];
+
+    // 盤面。
+
+    for index in (0..8).filter(|index| state.bit_boards[*index] != 0) {
+        let i = (4 * 3 - 1 - state.bit_boards[index].trailing_zeros()) as usize;
+
+        for piece_bit in (0..5).filter(|piece_bit| state.pieces[index] & 1 << piece_bit != 0) {
+            result[i][piece_bit] = 1.0;
+        }
+
+        result[i][5 + if index < 4 { 0 } else { 1 }] = 1.0;
+        result[i][5 + 2 + if state.ownership & 1 << index != 0 { 0 } else { 1 }] = 1.0;
+    }
+
+    // 自分の持ち駒と、敵の持ち駒。
+
+    for (owner, ownership) in [(0, state.ownership), (1, !state.ownership)] {
+        for (i, index) in (0..8).filter(|index| ownership & 1 << index != 0 && state.bit_boards[*index] == 0).enumerate() {
+            let i = if owner == 0 { 4 * 3 + i } else { 4 * 3 + 8 - 1 - i };
+
+            for piece_bit in (0..5).filter(|piece_bit| state.pieces[index] & 1 << piece_bit != 0) {
+                result[i][piece_bit] = 1.0;
+            }
+
+            result[i][5 + if index < 4 { 0 } else { 1 }] = 1.0;
+            result[i][5 + 2 + owner] = 1.0;
+        }
+    }
+
+    result.into_iter().flatten().collect()
+}
+
+// 観測結果の行（駒の特徴の9個の値）を取得します。
+
+fn row(observation: &[f32], i: usize) -> &[f32] {
+    &observation[i * 9..(i + 1) * 9]
+}
+
+// 盤面のマスと持ち駒が、レイアウトごとに決められた行になることを確認します。
+
+#[test]
+fn layouts_place_pieces_in_fixed_rows() {
+    assert_eq!(ObservationLayout::ALL.map(ObservationLayout::size), [20 * 9, 20 * 9, 81 * 4 * 3]);
+
+    // 先手の手番では、先手から見て左上のa1が0行目、右下のc4が11行目です。c4は、先手由来で手番側の「きりん」です。
+
+    let observation = ObservationLayout::RowsV2.encode(&Game::classical_initial_state());
+
+    assert_eq!(row(&observation, 11), [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+    assert_eq!(row(&observation, 0), [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+
+    // "rows-v2"では、持ち駒は12 + 駒の番号の行に入ります。"rows-v1"では、手番側の最初の持ち駒なので12行目です。
+
+    let state = HAND_STATE.parse::<State>().unwrap();
+    let hand_row = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+
+    assert_eq!(row(&ObservationLayout::RowsV2.encode(&state), 4 * 3 + 7), hand_row);
+    assert_eq!(row(&ObservationLayout::RowsV1.encode(&state), 4 * 3), hand_row);
+
+    // "planes-v1"では、持ち駒の特徴が9 + 駒の番号 * 9チャンネルの全てのマスに入ります。
+
+    let observation = ObservationLayout::PlanesV1.encode(&state);
+
+    assert!((0..4 * 3).all(|i| observation[(9 + 7 * 9) * 4 * 3 + i] == 1.0));
+    assert!((0..4 * 3).all(|i| observation[(9 + 6 * 9) * 4 * 3 + i] == 0.0));
+}
+
+// 持ち駒がある局面を"rows-v2"で変換して戻すと、元のステートと同じになることを確認します。
+
+#[test]
+fn rows_v2_decodes_hand_pieces() {
+    let state = HAND_STATE.parse::<State>().unwrap();
+    let decoded = ObservationLayout::RowsV2.decode(&ObservationLayout::RowsV2.encode(&state), state.turn).unwrap();
+
+    assert_eq!(decoded.notation().to_string(), HAND_STATE);
+    assert_eq!(decoded.to_stable_action((4 * 3, 3)), state.to_stable_action((4 * 3, 3)));
+}
+
+proptest! {
+    // ランダムな対局で、観測結果を変換して戻すと、同じ観測結果になることを確認します。
+
+    #[test]
+    fn observations_round_trip(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, _| {
+            for layout in ObservationLayout::ALL {
+                let observation = layout.encode(state);
+
+                prop_assert_eq!(observation.len(), layout.size());
+
+                // 駒のインデックスは観測結果に含まれないので、もう一度変換した観測結果で比較します。
+
+                let decoded = layout.decode(&observation, state.turn).unwrap();
+
+                prop_assert_eq!(decoded.check_invariants(), Ok(()), "{} {}", layout, state.notation());
+                prop_assert_eq!(layout.encode(&decoded), observation, "{} {}", layout, state.notation());
+            }
+
+            Ok(())
+        })?;
+    }
+
+    // ランダムな対局で、観測結果から作成したステートのアクションを、元のステートで実行できることを確認します。
+
+    #[test]
+    fn decoded_actions_are_accepted_by_the_original_state(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, action| {
+            // 観測結果から作成したステートで選んだアクション（持ち駒を打つアクションは、駒の番号で表現します）を、元のステートで実行できなければなりません。"rows-v1"は持ち駒の駒の番号がわからないので、対象外です。
+
+            for state in [*state, Game::next_state(state, action)] {
+                for layout in [ObservationLayout::RowsV2, ObservationLayout::PlanesV1] {
+                    let decoded = layout.decode(&layout.encode(&state), state.turn).unwrap();
+
+                    for decoded_action in Game::legal_actions(&decoded) {
+                        let action = decoded.to_stable_action(decoded_action).and_then(|stable_action| state.from_stable_action(stable_action));
+
+                        prop_assert!(action.is_some_and(|action| Game::try_next_state(&state, action).is_ok()), "{} {} {}", layout, state.notation(), decoded.action_notation(decoded_action));
+                    }
+                }
+            }
+
+            Ok(())
+        })?;
+    }
+
+    // ランダムな対局で、"rows-v1"が最初のバージョンのobservation()と同じになることを確認します。
+
+    #[test]
+    fn rows_v1_matches_the_first_observation(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, action| {
+            // 駒を取った直後の局面（持ち駒がある局面）もチェックします。
+
+            for state in [*state, Game::next_state(state, action)] {
+                prop_assert_eq!(ObservationLayout::RowsV1.encode(&state), first_observation(&state), "{}", state.notation());
+            }
+
+            Ok(())
+        })?;
+    }
+}
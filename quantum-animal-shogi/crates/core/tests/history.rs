@@ -0,0 +1,59 @@
+use quantum_animal_shogi_core::{DrawReason, GameRecord, Outcome, RuleSet};
+
+const CLASSICAL_INITIAL_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0";
+const QUANTUM_INITIAL_STATE: &str = "CGEL@c4 CGEL@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0";
+
+// 先手と後手が、右下と左上の駒を1マス進めて戻します。4手で、盤面が元に戻ります。
+
+const ROUND_TRIP: [&str; 4] = ["c4-c3", "a1-a2", "c3-c4", "a2-a1"];
+
+// 初期状態から、アクションを繰り返した後のゲームの結果を取得します。
+
+fn outcome<'a>(initial_state: &'a str, actions: impl IntoIterator<Item = &'a str>, rules: RuleSet) -> Outcome {
+    let text = [initial_state].into_iter().chain(actions).collect::<Vec<_>>().join("\n");
+
+    text.parse::<GameRecord>().unwrap().replay_with_rules(rules).unwrap().outcome()
+}
+
+// 同一局面がrepetition_count回現れたら、千日手で引き分けになることを確認します。
+
+#[test]
+fn repeated_positions_draw() {
+    // 初期状態が2回目に現れた時点では、まだ引き分けではありません。
+
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, ROUND_TRIP, RuleSet::STANDARD), Outcome::Ongoing);
+
+    // 3回目に現れたら、引き分けです。
+
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, ROUND_TRIP.repeat(2), RuleSet::STANDARD), Outcome::Draw(DrawReason::Repetition));
+
+    // repetition_countを変更すると、それに従います。
+
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, ROUND_TRIP, RuleSet { repetition_count: 2, ..RuleSet::STANDARD }), Outcome::Draw(DrawReason::Repetition));
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, ROUND_TRIP.repeat(2), RuleSet { repetition_count: 4, ..RuleSet::STANDARD }), Outcome::Ongoing);
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, ROUND_TRIP.repeat(4), RuleSet { repetition_count: 0, ..RuleSet::STANDARD }), Outcome::Ongoing);
+}
+
+// 盤面が同じでも、駒の可能性が異なる局面は別の局面になることを確認します。
+
+#[test]
+fn positions_differing_only_in_possibilities_do_not_repeat() {
+    // 最初の往復で、動かした駒から「ぞう」の可能性がなくなります。なので、初期状態は繰り返されません。
+
+    assert_eq!(outcome(QUANTUM_INITIAL_STATE, ROUND_TRIP.repeat(2), RuleSet::STANDARD), Outcome::Ongoing);
+
+    // 最初の往復の後の局面は、2回目と3回目の往復の後に繰り返されます。
+
+    assert_eq!(outcome(QUANTUM_INITIAL_STATE, ROUND_TRIP.repeat(3), RuleSet::STANDARD), Outcome::Draw(DrawReason::Repetition));
+}
+
+// 同一局面が現れなければ、千日手にならないことを確認します。
+
+#[test]
+fn distinct_positions_do_not_draw() {
+    // 全ての局面が異なるので、最も厳しい（2回目で千日手になる）ルールでも引き分けになりません。
+
+    let actions = ["c4-c3", "a1-a2", "b4-c4", "b1-a1", "c3-c2", "a2-a3"];
+
+    assert_eq!(outcome(CLASSICAL_INITIAL_STATE, actions, RuleSet { repetition_count: 2, ..RuleSet::STANDARD }), Outcome::Ongoing);
+}
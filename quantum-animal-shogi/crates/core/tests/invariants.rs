@@ -0,0 +1,76 @@
+mod common;
+
+use common::{play, rule_set, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, InvariantViolation};
+
+// 初期状態は不変条件を満たしていて、フィールドを壊すと、最初に見つかった違反がエラーになることを確認します。
+
+#[test]
+fn broken_states_violate_invariants() {
+    assert_eq!(Game::initial_state().check_invariants(), Ok(()));
+    assert_eq!(Game::classical_initial_state().check_invariants(), Ok(()));
+
+    // 駒の可能性が空。
+
+    let mut state = Game::classical_initial_state();
+
+    state.pieces[0] = 0;
+
+    assert_eq!(state.check_invariants(), Err(InvariantViolation::InvalidPiece(0)));
+
+    // 2つの駒が同じマス（c4）にある。
+
+    let mut state = Game::classical_initial_state();
+
+    state.bit_boards[1] = state.bit_boards[0];
+
+    assert_eq!(state.check_invariants(), Err(InvariantViolation::OverlappingBitBoards(0, 1)));
+
+    // c4の駒を「ライオン」に確定させたのに、「使い切り」で同じ由来の他の駒から「ライオン」の可能性を外していない。
+
+    let mut state = Game::initial_state();
+
+    state.pieces[0] = 0b0_1000;
+    state.hash = state.compute_hash();
+
+    assert_eq!(state.check_invariants(), Err(InvariantViolation::Uncollapsed { index: 1, infeasible: 0b0_1000 }));
+
+    // ハッシュ値を更新していない。
+
+    let mut state = Game::classical_initial_state();
+
+    state.hash ^= 1;
+
+    assert_eq!(state.check_invariants(), Err(InvariantViolation::Hash));
+}
+
+proptest! {
+    // ランダムな対局で、ステートの不変条件（State::check_invariants()）が崩れないことを確認します。
+
+    #[test]
+    fn random_games_keep_invariants(variant in variant(), rules in rule_set(), choices in vec(any::<usize>(), 0..256)) {
+        play(&rules, variant.initial_state(), &choices, |state, action| {
+            prop_assert_eq!(state.check_invariants(), Ok(()), "{}", state.notation());
+
+            let next_state = rules.next_state(state, action);
+
+            prop_assert_eq!(next_state.check_invariants(), Ok(()), "{} {}", state.notation(), state.action_notation(action));
+
+            Ok(())
+        })?;
+    }
+
+    // 合法手は、try_next_state()でも受け付けられることを確認します。
+
+    #[test]
+    fn legal_actions_are_accepted_by_try_next_state(variant in variant(), rules in rule_set(), choices in vec(any::<usize>(), 0..128)) {
+        play(&rules, variant.initial_state(), &choices, |state, _| {
+            for action in rules.legal_actions(state) {
+                prop_assert!(rules.try_next_state(state, action).is_ok(), "{} {}", state.notation(), state.action_notation(action));
+            }
+
+            Ok(())
+        })?;
+    }
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{play, rule_set, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, State};
+
+// 初期状態のキーを戻すと、初期状態になることを確認します。左右反転した局面は、キーは違っても、正規化したキーは同じです。
+
+#[test]
+fn initial_state_keys_round_trip() {
+    for state in [Game::initial_state(), Game::classical_initial_state()] {
+        let decoded = State::from_compact_key(state.compact_key(), state.turn).unwrap();
+
+        assert_eq!(decoded.notation().to_string(), state.notation().to_string());
+        assert_eq!(decoded.hash, state.hash);
+    }
+
+    let state = Game::classical_initial_state();
+
+    assert_ne!(state.mirrored().compact_key(), state.compact_key());
+    assert_eq!(state.mirrored().canonical_key(), state.canonical_key());
+
+    // 手数はキーに含まれないので、戻す時に指定します。
+
+    let decoded = State::from_compact_key(state.compact_key(), 2).unwrap();
+
+    assert_eq!(decoded.notation().to_string(), "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 2");
+}
+
+proptest! {
+    // ランダムな対局で、キーを戻すと同じキーになって、左右反転した局面と正規化したキーが同じになることを確認します。
+
+    #[test]
+    fn compact_keys_round_trip(variant in variant(), rules in rule_set(), choices in vec(any::<usize>(), 0..128)) {
+        play(&rules, variant.initial_state(), &choices, |state, action| {
+            // 終局した局面も置換表等に入るので、次の局面もチェックします。
+
+            for state in [*state, rules.next_state(state, action)] {
+                let key = state.compact_key();
+                let decoded = State::from_compact_key(key, state.turn);
+
+                prop_assert!(decoded.is_ok(), "{} {:?}", state.notation(), decoded);
+                prop_assert_eq!(decoded.unwrap().compact_key(), key, "{}", state.notation());
+
+                let canonical_key = state.canonical_key();
+
+                prop_assert_eq!(state.mirrored().canonical_key(), canonical_key);
+                prop_assert_eq!(State::from_compact_key(canonical_key, state.turn).unwrap().canonical_key(), canonical_key);
+            }
+
+            Ok(())
+        })?;
+    }
+}
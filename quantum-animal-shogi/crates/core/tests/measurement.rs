@@ -0,0 +1,56 @@
+use quantum_animal_shogi_core::{Game, MeasurementError, State};
+
+// 観測した駒が確定して、「使い切り」で同じ由来の他の駒から、その種類の可能性が外れることを確認します。
+
+#[test]
+fn measurement_collapses_other_pieces() {
+    let state = Game::measure(&Game::initial_state(), 0, 0b0_1000).unwrap();
+
+    assert_eq!(state.pieces, [0b0_1000, 0b0_0111, 0b0_0111, 0b0_0111, 0b0_1111, 0b0_1111, 0b0_1111, 0b0_1111]);
+    assert_eq!(state.marginals().world_count, 6 * 24);
+
+    // 複数の種類に絞り込むこともできます。
+
+    let state = Game::measure(&Game::initial_state(), 0, 0b0_0011).unwrap();
+
+    assert_eq!(state.pieces[0], 0b0_0011);
+    assert_eq!(state.pieces[1], 0b0_1111);
+}
+
+// 可能性がない種類に観測すると、エラーになることを確認します。
+
+#[test]
+fn impossible_measurement_is_rejected() {
+    assert_eq!(Game::measure(&Game::initial_state(), 0, 0b1_0000).err(), Some(MeasurementError::Impossible { index: 0, piece: 0b1_0000 }));
+    assert_eq!(Game::measure(&Game::classical_initial_state(), 0, 0b0_1000).err(), Some(MeasurementError::Impossible { index: 0, piece: 0b0_1000 }));
+}
+
+// 他の駒と矛盾する種類に観測すると、エラーになることを確認します。
+
+#[test]
+fn contradicting_measurement_is_rejected() {
+    // c4とb4の駒は「きりん」か「ぞう」なので、a4の駒が「きりん」だと、2つの駒が「ぞう」になってしまいます。
+
+    let state = "GE@c4 GE@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0".parse::<State>().unwrap();
+
+    assert_eq!(Game::measure(&state, 2, 0b0_0010).err(), Some(MeasurementError::Contradiction { index: 2, piece: 0b0_0010 }));
+    assert!(Game::measure(&state, 2, 0b0_0001).is_ok());
+}
+
+// 矛盾しない種類ごとに、観測した状態を取得できることを確認します。
+
+#[test]
+fn measurements_skip_contradictions() {
+    let piece_bits = |state: &State, index| Game::measurements(state, index).map(|(piece_bit, _)| piece_bit).collect::<Vec<_>>();
+
+    assert_eq!(piece_bits(&Game::initial_state(), 0), vec![0, 1, 2, 3]);
+    assert_eq!(piece_bits(&Game::classical_initial_state(), 0), vec![1]);
+
+    let state = "GE@c4 GE@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0".parse::<State>().unwrap();
+
+    assert_eq!(piece_bits(&state, 2), vec![0, 3]);
+
+    // 観測した状態の可能世界の数の合計は、観測前の可能世界の数と同じです。
+
+    assert_eq!(Game::measurements(&state, 2).map(|(_, state)| state.marginals().world_count).sum::<usize>(), state.marginals().world_count);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{play, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, ParseStateError, RuleSet, State};
+
+const QUANTUM_INITIAL_STATE: &str = "CGEL@c4 CGEL@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0";
+const CLASSICAL_INITIAL_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0";
+
+// 後手の手番で、先手が「にわとり」をa1に成らせていて、後手の「ひよこ」を持ち駒にしている局面です。
+
+const HAND_AND_PROMOTED_STATE: &str = "G@c4 L@b4 E@a4 H@a1 / C@* e@c2 l@b1 g@c1 3";
+
+fn parse(notation: &str) -> Result<State, ParseStateError> {
+    notation.parse()
+}
+
+// 初期状態の表記を確認します。
+
+#[test]
+fn initial_states_have_fixed_notations() {
+    assert_eq!(Game::initial_state().notation().to_string(), QUANTUM_INITIAL_STATE);
+    assert_eq!(Game::classical_initial_state().notation().to_string(), CLASSICAL_INITIAL_STATE);
+
+    let state = parse(QUANTUM_INITIAL_STATE).unwrap();
+    let initial_state = Game::initial_state();
+
+    assert_eq!((state.pieces, state.ownership, state.bit_boards, state.turn, state.hash), (initial_state.pieces, initial_state.ownership, initial_state.bit_boards, initial_state.turn, initial_state.hash));
+}
+
+// 持ち駒と成った駒がある、後手の手番の局面を解析します。Stateは手番側（後手）から見たものになります。
+
+#[test]
+fn hand_and_promoted_pieces_are_parsed() {
+    let state = parse(HAND_AND_PROMOTED_STATE).unwrap();
+
+    assert_eq!(state.pieces, [0b_0000_0010, 0b_0000_1000, 0b_0000_0100, 0b_0001_0000, 0b_0000_0001, 0b_0000_0100, 0b_0000_1000, 0b_0000_0010]);
+    assert_eq!(state.ownership, 0b_1110_0000);
+    assert_eq!(state.bit_boards, [1 << 11, 1 << 10, 1 << 9, 1 << 0, 0, 1 << 5, 1 << 1, 1 << 2]);
+    assert_eq!(state.turn, 3);
+    assert_eq!(state.hash, state.compute_hash());
+
+    assert_eq!(state.notation().to_string(), HAND_AND_PROMOTED_STATE);
+}
+
+// アクションの表記は、先手から見たマスになることを確認します。
+
+#[test]
+fn actions_have_fixed_notations() {
+    let state = parse(CLASSICAL_INITIAL_STATE).unwrap();
+
+    assert_eq!(state.action_notation((4, 7)), "b3-b2");
+
+    // 後手の手番では、マスを回転します。
+
+    let state = parse(HAND_AND_PROMOTED_STATE).unwrap();
+
+    assert_eq!(state.action_notation((1, 4)), "b1-b2");
+    assert_eq!(state.action_notation((4 * 3, 3)), "*0-a2");
+}
+
+// 解析できない表記が、エラーになることを確認します。
+
+#[test]
+fn malformed_notations_are_rejected() {
+    // 不明な動物。
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 X@b3 / c@b2 e@c1 l@b1 g@a1 0").err(), Some(ParseStateError::Piece("X@b3".to_string())));
+
+    // 不正な可能性の集合（空、重複、大文字と小文字の混在）。
+
+    for piece in ["@b3", "CC@b3", "Cg@b3"] {
+        let notation = format!("G@c4 L@b4 E@a4 {} / c@b2 e@c1 l@b1 g@a1 0", piece);
+
+        assert_eq!(parse(&notation).err(), Some(ParseStateError::Piece(piece.to_string())));
+    }
+
+    // 不正なマス。
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 C@d3 / c@b2 e@c1 l@b1 g@a1 0").err(), Some(ParseStateError::Piece("C@d3".to_string())));
+
+    // 同じマスに複数の駒。
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 C@b3 / c@b3 e@c1 l@b1 g@a1 0").err(), Some(ParseStateError::DuplicateSquare("b3".to_string())));
+
+    // 手数がない（最後の駒が手数として解析されます）、手数が数値ではない。
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1").err(), Some(ParseStateError::Turn("g@a1".to_string())));
+    assert_eq!(parse("G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 -1").err(), Some(ParseStateError::Turn("-1".to_string())));
+
+    // "/"がない、駒の数が4つではない。
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 C@b3 c@b2 e@c1 l@b1 g@a1 0").err(), Some(ParseStateError::Format));
+    assert_eq!(parse("G@c4 L@b4 E@a4 / C@b3 c@b2 e@c1 l@b1 g@a1 0").err(), Some(ParseStateError::Format));
+}
+
+proptest! {
+    // ランダムな対局で、表記を解析すると元の局面に戻ることを確認します。
+
+    #[test]
+    fn notation_round_trips(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, _| {
+            let parsed = state.notation().to_string().parse::<State>().unwrap();
+
+            prop_assert_eq!(parsed.notation().to_string(), state.notation().to_string());
+            prop_assert_eq!(parsed.hash, state.hash);
+
+            Ok(())
+        })?;
+    }
+}
@@ -0,0 +1,54 @@
+use quantum_animal_shogi_core::{DrawReason, Game, GameRecord, Outcome, Reason, RuleSet, State, TryRule};
+
+// 手番側のライオンが敵陣にいれば、トライの成功で勝ちになることを確認します。トライを無効にしたルールでは、勝ちになりません。
+
+#[test]
+fn lion_on_last_rank_wins_by_try() {
+    let state = "G@c4 L@c1 E@a4 C@b3 / c@b2 e@a2 l@a3 g@a1 0".parse::<State>().unwrap();
+
+    assert_eq!(Game::outcome(&state), Outcome::Win(Reason::Try));
+    assert_eq!(RuleSet { try_rule: TryRule::Disabled, ..RuleSet::STANDARD }.outcome(&state), Outcome::Ongoing);
+}
+
+// ライオンを取られた側が、キャッチで負けになることを確認します。
+
+#[test]
+fn captured_lion_loses_by_catch() {
+    let record = "G@c4 L@b4 E@a4 C@b3 / c@a2 e@c1 l@b2 g@a1 0\nb3-b2".parse::<GameRecord>().unwrap();
+    let state = Game::next_state(&record.initial_state, record.actions[0]);
+
+    // 結果は次の手番側から見たものなので、ライオンを取った側から見ると勝ちです。
+
+    assert_eq!(Game::outcome(&state), Outcome::Loss(Reason::Catch));
+    assert_eq!(Game::outcome(&state).reversed(), Outcome::Win(Reason::Catch));
+}
+
+// 手数がmax_turnになると、引き分けになることを確認します。
+
+#[test]
+fn turn_limit_draws() {
+    let state = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 256".parse::<State>().unwrap();
+
+    assert_eq!(Game::outcome(&state), Outcome::Draw(DrawReason::TurnLimit));
+    assert_eq!(RuleSet { max_turn: 257, ..RuleSet::STANDARD }.outcome(&state), Outcome::Ongoing);
+}
+
+// 終了の判定と、相手側から見た結果と、表示を確認します。
+
+#[test]
+fn outcomes_reverse_and_display() {
+    let outcomes = [
+        (Outcome::Ongoing,                      Outcome::Ongoing,                      false, "ongoing"),
+        (Outcome::Win(Reason::Try),             Outcome::Loss(Reason::Try),            true,  "win by try"),
+        (Outcome::Loss(Reason::Catch),          Outcome::Win(Reason::Catch),           true,  "loss by catch"),
+        (Outcome::Draw(DrawReason::TurnLimit),  Outcome::Draw(DrawReason::TurnLimit),  true,  "draw by turn limit"),
+        (Outcome::Draw(DrawReason::Repetition), Outcome::Draw(DrawReason::Repetition), true,  "draw by repetition")
+    ];
+
+    for (outcome, reversed, is_over, text) in outcomes {
+        assert_eq!(outcome.reversed(), reversed);
+        assert_eq!(outcome.reversed().reversed(), outcome);
+        assert_eq!(outcome.is_over(), is_over);
+        assert_eq!(outcome.to_string(), text);
+    }
+}
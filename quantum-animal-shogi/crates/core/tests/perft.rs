@@ -0,0 +1,55 @@
+use quantum_animal_shogi_core::{Action, Game, Square};
+
+// 初期状態からの、深さごとの末端の局面の数です。合法手の生成（NEXTS）や収束（収縮？）、盤面の回転を変更したら、値が変わっていないことを確認してください。
+
+const QUANTUM_COUNTS: [u64; 6] = [9, 79, 831, 9_266, 101_837, 1_173_742];
+const CLASSICAL_COUNTS: [u64; 6] = [4, 17, 123, 976, 8_122, 72_427];
+
+#[test]
+fn quantum_perft() {
+    for (depth, count) in (1..=5).zip(QUANTUM_COUNTS) {
+        assert_eq!(Game::perft(&Game::initial_state(), depth), count, "depth {}", depth);
+    }
+}
+
+// 最適化なしのビルドだと時間がかかるので、`cargo test --release -- --ignored`で実行してください。
+
+#[test]
+#[ignore]
+fn quantum_perft_deep() {
+    assert_eq!(Game::perft(&Game::initial_state(), 6), QUANTUM_COUNTS[5]);
+}
+
+#[test]
+fn classical_perft() {
+    for (depth, count) in (1..=6).zip(CLASSICAL_COUNTS) {
+        assert_eq!(Game::perft(&Game::classical_initial_state(), depth), count, "depth {}", depth);
+    }
+}
+
+#[test]
+fn perft_divide_sums_to_perft() {
+    for state in [Game::initial_state(), Game::classical_initial_state()] {
+        let counts = Game::perft_divide(&state, 4);
+
+        assert_eq!(counts.len() as u64, Game::perft(&state, 1));
+        assert_eq!(counts.into_iter().map(|(_, count)| count).sum::<u64>(), Game::perft(&state, 4));
+    }
+}
+
+// どうぶつしょうぎの初期状態から深さ2で、ルートのアクションごとの数（後手の合法手の数）を確認します。
+
+#[test]
+fn classical_perft_divide() {
+    let square = |bit| Square::new(bit).unwrap();
+
+    assert_eq!(
+        Game::perft_divide(&Game::classical_initial_state(), 2),
+        vec![
+            (Action::Move { from: square(0), to: square(3) }, 4),  // c4-c3
+            (Action::Move { from: square(1), to: square(3) }, 4),  // b4-c3
+            (Action::Move { from: square(1), to: square(5) }, 4),  // b4-a3
+            (Action::Move { from: square(4), to: square(7) }, 5)   // b3-b2
+        ]
+    );
+}
@@ -0,0 +1,88 @@
+use quantum_animal_shogi_core::{GameRecord, IllegalAction, Outcome, ParseRecordError, ParseStateError, Reason, ReplayError, Square};
+
+const CLASSICAL_INITIAL_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0";
+
+// 先手の「ひよこ」が後手の「ひよこ」を取り、後手の「ぞう」がその「ひよこ」を取り返して、先手が取った「ひよこ」を打つ棋譜です。
+
+const RECORD: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0\nb3-b2\nc1-b2\n*0-a3\n";
+
+// 先手の「ライオン」が、後手の「ライオン」を取れる局面です。
+
+const CATCH_STATE: &str = "G@c4 L@b3 E@a4 C@c3 / c@a2 e@c1 l@b2 g@a1 0";
+
+fn parse(text: &str) -> Result<GameRecord, ParseRecordError> {
+    text.parse()
+}
+
+// 文字列にして解析すると、元の棋譜に戻ることを確認します。
+
+#[test]
+fn records_round_trip() {
+    let record = parse(RECORD).unwrap();
+
+    assert_eq!(record.initial_state.notation().to_string(), CLASSICAL_INITIAL_STATE);
+
+    // アクションは、手番側から見たものになります（2手目のc1-b2は、後手から見た(2, 4)です）。
+
+    assert_eq!(record.actions, vec![(4, 7), (2, 4), (4 * 3, 5)]);
+    assert_eq!(record.to_string(), RECORD);
+
+    // 再生した履歴の棋譜も、同じ文字列になります。
+
+    let history = record.replay().unwrap();
+
+    assert_eq!(history.record().to_string(), RECORD);
+    assert_eq!(history.state().notation().to_string(), "G@c4 L@b4 E@a4 c@* / C@a3 e@b2 l@b1 g@a1 3");
+    assert_eq!(record.states().unwrap().len(), 1 + 3);
+}
+
+// コメントと空行が無視されることを確認します。
+
+#[test]
+fn comments_and_blank_lines_are_ignored() {
+    let text = "# 先手: A\n# 後手: B\n\n  G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0  # 初期状態\n\nb3-b2  # 取る\n   \nc1-b2\n*0-a3\n# 終わり\n";
+
+    assert_eq!(parse(text).unwrap().to_string(), RECORD);
+}
+
+// 解析できない行が、行番号（コメントと空行も数えた、1始まりの番号）付きのエラーになることを確認します。
+
+#[test]
+fn malformed_lines_are_rejected() {
+    assert_eq!(parse("").err(), Some(ParseRecordError::Empty));
+    assert_eq!(parse("# コメントだけ\n\n").err(), Some(ParseRecordError::Empty));
+
+    assert_eq!(parse("G@c4 L@b4 E@a4 / c@b2 e@c1 l@b1 g@a1 0").err(), Some(ParseRecordError::State { line_number: 1, error: ParseStateError::Format }));
+    assert_eq!(parse("# コメント\nG@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 x").err(), Some(ParseRecordError::State { line_number: 2, error: ParseStateError::Turn("x".to_string()) }));
+
+    for action in ["b3-b5", "d3-b2", "b3b2", "*8-a3", "*x-a3", "*0"] {
+        let text = format!("{}\n\nb3-b2\n{}\n", CLASSICAL_INITIAL_STATE, action);
+
+        assert_eq!(parse(&text).err(), Some(ParseRecordError::Action { line_number: 4, action: action.to_string() }));
+    }
+}
+
+// 合法手ではないアクションを再生すると、IllegalActionになることを確認します。
+
+#[test]
+fn illegal_actions_are_not_replayed() {
+    // 2手目で、後手が先手の「ひよこ」を動かそうとします。マスは、手番側（後手）から見たものになります。
+
+    let record = parse(&format!("{}\nb3-b2\nb2-b1\n", CLASSICAL_INITIAL_STATE)).unwrap();
+
+    assert_eq!(record.replay().err(), Some(ReplayError::IllegalAction { ply: 1, error: IllegalAction::OpponentPiece(Square::new(4).unwrap()) }));
+    assert_eq!(record.states().err(), Some(ReplayError::IllegalAction { ply: 1, error: IllegalAction::OpponentPiece(Square::new(4).unwrap()) }));
+}
+
+// 勝敗が決した後のアクションを再生すると、GameOverになることを確認します。
+
+#[test]
+fn actions_after_the_game_is_over_are_not_replayed() {
+    let record = parse(&format!("{}\nb3-b2\n", CATCH_STATE)).unwrap();
+
+    assert_eq!(record.replay().unwrap().outcome(), Outcome::Loss(Reason::Catch));
+
+    let record = parse(&format!("{}\nb3-b2\nc1-c2\n", CATCH_STATE)).unwrap();
+
+    assert_eq!(record.replay().err(), Some(ReplayError::GameOver { ply: 1 }));
+}
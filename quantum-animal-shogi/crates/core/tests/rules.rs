@@ -0,0 +1,119 @@
+use quantum_animal_shogi_core::{DrawReason, GameHistory, GameRecord, Outcome, Reason, RuleSet, State, TryRule};
+
+// 先手の「ライオン」がb2にいて、b1（後手の「ライオン」が利いている）とc1（どの駒も利いていない）にトライできる局面です。
+
+const TRY_STATE: &str = "G@c4 L@b2 E@a4 C@b3 / c@a3 e@b4 l@a1 g@a2 0";
+
+// 先手の「ひよこ」が、b1に移動できる局面です。
+
+const PROMOTION_STATE: &str = "G@c4 L@b4 E@a4 C@b2 / c@c3 e@c1 l@a1 g@a2 0";
+
+// 先手が、「ひよこ」か「きりん」の持ち駒を持っている局面です。c1が空いています。
+
+const DROP_STATE: &str = "G@c4 L@b4 E@a4 C@b2 / CG@* e@c2 l@b1 cg@a1 2";
+
+fn state(notation: &str) -> State {
+    notation.parse().unwrap()
+}
+
+// 棋譜を、ルールを指定して再生します。
+
+fn replay(text: &str, rules: RuleSet) -> GameHistory {
+    text.parse::<GameRecord>().unwrap().replay_with_rules(rules).unwrap()
+}
+
+// TryRule::SafeOnlyでは、敵の駒が利いているマスにはトライできないことを確認します。
+
+#[test]
+fn safe_only_forbids_tries_onto_attacked_squares() {
+    let state = state(TRY_STATE);
+
+    // 標準のルールなら、b1（10）とc1（9）のどちらにも移動できます。
+
+    assert!(RuleSet::STANDARD.legal_actions(&state).any(|action| action == (7, 10)));
+    assert!(RuleSet::STANDARD.legal_actions(&state).any(|action| action == (7, 9)));
+
+    // SafeOnlyなら、c1にしか移動できません。
+
+    let rules = RuleSet { try_rule: TryRule::SafeOnly, ..RuleSet::STANDARD };
+
+    assert!(!rules.legal_actions(&state).any(|action| action == (7, 10)));
+    assert!(rules.legal_actions(&state).any(|action| action == (7, 9)));
+    assert!(rules.try_next_state(&state, (7, 10)).is_err());
+
+    // 「きりん」か「ライオン」の駒は、b1に移動すると「きりん」になり（「ライオン」の可能性が外れます）、「使い切り」でc4の駒が「ライオン」になります。
+
+    let state = "GL@c4 GL@b2 E@a4 C@b3 / c@a3 e@b4 l@a1 g@a2 0".parse::<State>().unwrap();
+
+    assert_eq!(RuleSet::STANDARD.next_state(&state, (7, 10)).notation().to_string(), "GL@c4 GL@b1 E@a4 C@b3 / c@a3 e@b4 l@a1 g@a2 1");
+    assert_eq!(rules.next_state(&state, (7, 10)).notation().to_string(), "L@c4 G@b1 E@a4 C@b3 / c@a3 e@b4 l@a1 g@a2 1");
+}
+
+// TryRule::Disabledでは、トライしても勝ちにならないことを確認します。
+
+#[test]
+fn disabled_try_rule_does_not_win() {
+    // 先手の「ライオン」がc1にトライして、後手が「きりん」を動かした後の結果です。
+
+    let text = format!("{}\nb2-c1\na2-b2", TRY_STATE);
+
+    assert_eq!(replay(&text, RuleSet::STANDARD).outcome(), Outcome::Win(Reason::Try));
+    assert_eq!(replay(&text, RuleSet { try_rule: TryRule::Disabled, ..RuleSet::STANDARD }).outcome(), Outcome::Ongoing);
+}
+
+// promotionがfalseなら、「ひよこ」が敵のエリアに移動しても「にわとり」に成らないことを確認します。
+
+#[test]
+fn chicks_do_not_promote_without_promotion() {
+    let state = state(PROMOTION_STATE);
+
+    assert_eq!(RuleSet::STANDARD.next_state(&state, (7, 10)).notation().to_string(), "G@c4 L@b4 E@a4 H@b1 / c@c3 e@c1 l@a1 g@a2 1");
+    assert_eq!(RuleSet { promotion: false, ..RuleSet::STANDARD }.next_state(&state, (7, 10)).notation().to_string(), "G@c4 L@b4 E@a4 C@b1 / c@c3 e@c1 l@a1 g@a2 1");
+}
+
+// chick_drop_on_last_rankがfalseなら、敵のエリアに打った駒から「ひよこ」の可能性が外れることを確認します。
+
+#[test]
+fn chicks_are_not_dropped_on_the_last_rank() {
+    let state = state(DROP_STATE);
+    let rules = RuleSet { chick_drop_on_last_rank: false, ..RuleSet::STANDARD };
+
+    // 標準のルールなら、可能性は変わりません。
+
+    assert_eq!(RuleSet::STANDARD.next_state(&state, (4 * 3, 9)).notation().to_string(), "G@c4 L@b4 E@a4 C@b2 / CG@c1 e@c2 l@b1 cg@a1 3");
+
+    // 打った駒は「きりん」になり、「使い切り」で残りの駒が「ひよこ」になります。
+
+    assert_eq!(rules.next_state(&state, (4 * 3, 9)).notation().to_string(), "G@c4 L@b4 E@a4 C@b2 / G@c1 e@c2 l@b1 c@a1 3");
+
+    // 「ひよこ」の可能性しかない駒は、敵のエリアには打てません。
+
+    let state = "G@c4 L@b4 E@a4 C@b2 / C@* e@c2 l@b1 g@a1 2".parse::<State>().unwrap();
+
+    assert!(RuleSet::STANDARD.legal_actions(&state).any(|action| action == (4 * 3, 9)));
+    assert!(!rules.legal_actions(&state).any(|action| action == (4 * 3, 9)));
+    assert!(rules.legal_actions(&state).any(|action| action == (4 * 3, 3)));
+}
+
+// max_turnを変更すると、その手数で引き分けになることを確認します。
+
+#[test]
+fn custom_max_turn_draws() {
+    let text = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 10\nc4-c3";
+
+    assert_eq!(replay(text, RuleSet::STANDARD).outcome(), Outcome::Ongoing);
+    assert_eq!(replay(text, RuleSet { max_turn: 11, ..RuleSet::STANDARD }).outcome(), Outcome::Draw(DrawReason::TurnLimit));
+    assert_eq!(replay(text, RuleSet { max_turn: 12, ..RuleSet::STANDARD }).outcome(), Outcome::Ongoing);
+}
+
+// repetition_countを変更すると、その回数で千日手になることを確認します。
+
+#[test]
+fn custom_repetition_count_draws() {
+    // 4手で、盤面が元に戻ります（初期状態が2回目に現れます）。
+
+    let text = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0\nc4-c3\na1-a2\nc3-c4\na2-a1";
+
+    assert_eq!(replay(text, RuleSet::STANDARD).outcome(), Outcome::Ongoing);
+    assert_eq!(replay(text, RuleSet { repetition_count: 2, ..RuleSet::STANDARD }).outcome(), Outcome::Draw(DrawReason::Repetition));
+}
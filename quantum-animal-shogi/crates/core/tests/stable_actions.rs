@@ -0,0 +1,63 @@
+mod common;
+
+use common::{play, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Action, Game, RuleSet, State};
+
+// 後手の手番で、後手が先手由来の「ひよこ」（インデックス3）を持ち駒にしている局面です。
+
+const HAND_STATE: &str = "G@c4 L@b4 E@a4 c@* / C@a3 e@b2 l@b1 g@a1 3";
+
+// 持ち駒を打つアクションの移動元が、12 + 手番側から見た駒の番号になることを確認します。
+
+#[test]
+fn stable_actions_use_piece_ids() {
+    let state = HAND_STATE.parse::<State>().unwrap();
+
+    // 後手から見ると、先手由来の駒は敵由来なので、インデックス3の駒の番号は7です。
+
+    assert_eq!(state.piece_id(3), 7);
+    assert_eq!(state.to_stable_action((4 * 3, 3)), Some((4 * 3 + 7, 3)));
+    assert_eq!(state.from_stable_action((4 * 3 + 7, 3)), Some((4 * 3, 3)));
+
+    // 駒の番号0（インデックス4）の駒は盤上にあるので、持ち駒を打つアクションにはなりません。
+
+    assert_eq!(state.from_stable_action((4 * 3, 3)), None);
+    assert_eq!(state.from_stable_action((4 * 3 + 8, 3)), None);
+
+    // 駒を指すアクションは、そのままです。
+
+    assert_eq!(state.to_stable_action((4, 7)), Some((4, 7)));
+    assert_eq!(state.from_stable_action((4, 7)), Some((4, 7)));
+}
+
+// 持ち駒がない場合は、Noneになることを確認します。
+
+#[test]
+fn to_stable_action_rejects_missing_hand_slots() {
+    let state = Game::initial_state();
+
+    assert_eq!(state.to_stable_action((11, 8)), Some((11, 8)));
+    assert_eq!(state.to_stable_action((4 * 3, 6)), None);
+    assert_eq!(state.to_stable_action((u8::MAX, 6)), None);
+
+    assert_eq!(HAND_STATE.parse::<State>().unwrap().to_stable_action((4 * 3 + 1, 3)), None);
+}
+
+proptest! {
+    // ランダムな対局で、合法手が安定した表現を経由して元に戻り、Python側のインデックスにできることを確認します。
+
+    #[test]
+    fn stable_actions_round_trip(variant in variant(), choices in vec(any::<usize>(), 0..128)) {
+        play(&RuleSet::STANDARD, variant.initial_state(), &choices, |state, _| {
+            for action in Game::legal_actions(state) {
+                let stable_action = state.to_stable_action(action).unwrap();
+
+                prop_assert_eq!(state.from_stable_action(stable_action), Some(action));
+                prop_assert!(Action::try_from(stable_action).unwrap().index() < Action::INDEX_COUNT);
+            }
+
+            Ok(())
+        })?;
+    }
+}
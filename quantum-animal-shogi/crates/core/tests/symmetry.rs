@@ -0,0 +1,56 @@
+mod common;
+
+use common::{play, rule_set, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Action, Game, Square};
+
+// 盤面を左右反転すると、a筋とc筋が入れ替わることを確認します。
+
+#[test]
+fn mirroring_swaps_files() {
+    let state = Game::classical_initial_state();
+
+    assert_eq!(state.mirrored().notation().to_string(), "G@a4 L@b4 E@c4 C@b3 / c@b2 e@a1 l@b1 g@c1 0");
+    assert_eq!(state.mirrored().hash, state.mirrored().compute_hash());
+
+    // c4-c3（0から3）は、a4-a3（2から5）になります。持ち駒を打つアクションは、打つマスだけが変わります。
+
+    let square = |bit| Square::new(bit).unwrap();
+
+    assert_eq!(Action::Move { from: square(0), to: square(3) }.mirrored(), Action::Move { from: square(2), to: square(5) });
+    assert_eq!(Action::Drop { hand_slot: 1, to: square(4) }.mirrored(), Action::Drop { hand_slot: 1, to: square(4) });
+    assert_eq!(Action::mirrored_index(Action::Move { from: square(0), to: square(3) }.index()), Some(Action::Move { from: square(2), to: square(5) }.index()));
+}
+
+// Python側のインデックスの左右反転を2回繰り返すと、元に戻ることを確認します。
+
+#[test]
+fn mirrored_indices_are_an_involution() {
+    for index in 0..Action::INDEX_COUNT {
+        let mirrored_index = Action::mirrored_index(index).unwrap();
+
+        assert!(mirrored_index < Action::INDEX_COUNT);
+        assert_eq!(Action::mirrored_index(mirrored_index), Some(index));
+    }
+
+    assert_eq!(Action::mirrored_index(Action::INDEX_COUNT), None);
+}
+
+proptest! {
+    // ランダムな対局で、左右反転とnext_state()の順番を入れ替えても、同じ結果になることを確認します。
+
+    #[test]
+    fn mirroring_commutes_with_next_state(variant in variant(), rules in rule_set(), choices in vec(any::<usize>(), 0..128)) {
+        play(&rules, variant.initial_state(), &choices, |state, action| {
+            let mirrored = state.mirrored();
+            let mirrored_action = Action::try_from(action).unwrap().mirrored().into();
+
+            prop_assert_eq!(mirrored.check_invariants(), Ok(()), "{}", state.notation());
+            prop_assert_eq!(mirrored.mirrored().notation().to_string(), state.notation().to_string());
+            prop_assert!(rules.legal_actions(&mirrored).any(|other| other == mirrored_action), "{} {}", state.notation(), state.action_notation(action));
+            prop_assert_eq!(rules.next_state(&mirrored, mirrored_action).notation().to_string(), rules.next_state(state, action).mirrored().notation().to_string());
+
+            Ok(())
+        })?;
+    }
+}
@@ -0,0 +1,67 @@
+use quantum_animal_shogi_core::{CollapseEvent, Game, GameRecord, State};
+
+const QUANTUM_INITIAL_STATE: &str = "CGEL@c4 CGEL@b4 CGEL@a4 CGEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0";
+
+// 状態とアクションを解析して、次の状態と、駒の可能性が減った理由のリストを取得します。
+
+fn next_state_with_trace(state: &str, action: &str) -> (State, Vec<CollapseEvent>) {
+    let record = format!("{}\n{}", state, action).parse::<GameRecord>().unwrap();
+
+    Game::next_state_with_trace(&record.initial_state, record.actions[0])
+}
+
+// 前に進めない「ぞう」の可能性が、「絞り込み」で外れることを確認します。
+
+#[test]
+fn moves_narrow_the_moved_piece() {
+    let (next_state, events) = next_state_with_trace(QUANTUM_INITIAL_STATE, "c4-c3");
+
+    assert_eq!(events, vec![CollapseEvent::Narrowed { index: 0, before: 0b0_1111, after: 0b0_1011 }]);
+    assert_eq!(next_state.pieces, Game::next_state(&Game::initial_state(), (0, 3)).pieces);
+}
+
+// 「絞り込み」で確定した種類が、「使い切り」で他の駒から外れることを確認します。
+
+#[test]
+fn narrowing_triggers_pigeonhole() {
+    // 「ライオン」はb3の駒で使い切られています。c4の駒を前に進めると「きりん」に確定するので、b4の駒は「ぞう」に確定します。
+
+    let (next_state, events) = next_state_with_trace("GE@c4 GEL@b4 C@a4 L@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0", "c4-c3");
+
+    assert_eq!(
+        events,
+        vec![
+            CollapseEvent::Narrowed { index: 0, before: 0b0_0110, after: 0b0_0010 },
+            CollapseEvent::Pigeonhole { index: 1, target: 0b0_0010, before: 0b0_1110, after: 0b0_1100 },
+            CollapseEvent::Pigeonhole { index: 1, target: 0b0_1000, before: 0b0_1100, after: 0b0_0100 }
+        ]
+    );
+    assert_eq!(next_state.pieces[1], 0b0_0100);
+    assert!(events.iter().all(|event| event.index() <= 1));
+}
+
+// 取った駒から、ライオンの可能性が外れることを確認します。敵の駒に、ライオンの可能性が残っているためです。
+
+#[test]
+fn captured_pieces_cannot_be_lions() {
+    let (next_state, events) = next_state_with_trace(QUANTUM_INITIAL_STATE, "b3-b2");
+
+    assert_eq!(
+        events,
+        vec![
+            CollapseEvent::Narrowed { index: 3, before: 0b0_1111, after: 0b0_1011 },
+            CollapseEvent::LionRemovedFromHand { index: 4, before: 0b0_1111, after: 0b0_0111 }
+        ]
+    );
+    assert_eq!(next_state.pieces[4], 0b0_0111);
+    assert_eq!(next_state.bit_boards[4], 0);
+}
+
+// 全ての駒が確定していれば、駒の可能性は減らないことを確認します。
+
+#[test]
+fn classical_moves_have_no_events() {
+    let (_, events) = next_state_with_trace("G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0", "b3-b2");
+
+    assert_eq!(events, vec![]);
+}
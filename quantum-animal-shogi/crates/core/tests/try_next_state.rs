@@ -0,0 +1,97 @@
+use quantum_animal_shogi_core::{Game, IllegalAction, InvalidAction, RuleSet, Square, State};
+
+// どうぶつしょうぎの初期状態です。マスのビットの位置は、c4が0、b4が1、a4が2、……、a1が11になります。
+
+const CLASSICAL_INITIAL_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0";
+
+// 先手が後手の「ひよこ」を取って、持ち駒にした局面です。c1が空いています。
+
+const HAND_STATE: &str = "G@c4 L@b4 E@a4 C@b2 / C@* e@c2 l@b1 g@a1 2";
+
+fn state(notation: &str) -> State {
+    notation.parse().unwrap()
+}
+
+fn square(bit: u8) -> Square {
+    Square::new(bit).unwrap()
+}
+
+// アクションとして解釈できない場合は、Invalidになることを確認します。
+
+#[test]
+fn invalid_actions_are_rejected() {
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (4, 4 * 3)).err(), Some(IllegalAction::Invalid(InvalidAction((4, 4 * 3)))));
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (4 * 3 + 8, 6)).err(), Some(IllegalAction::Invalid(InvalidAction((4 * 3 + 8, 6)))));
+}
+
+// 移動元に駒がない場合は、EmptySquareになることを確認します。
+
+#[test]
+fn moves_from_empty_squares_are_rejected() {
+    // c3からc2。
+
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (3, 6)).err(), Some(IllegalAction::EmptySquare(square(3))));
+}
+
+// 移動元の駒が敵の駒の場合は、OpponentPieceになることを確認します。
+
+#[test]
+fn moves_of_opponent_pieces_are_rejected() {
+    // 後手の「ひよこ」を、b2からb3。
+
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (7, 4)).err(), Some(IllegalAction::OpponentPiece(square(7))));
+}
+
+// 移動先に自分の駒がある場合と、打つ先に駒がある場合は、OccupiedSquareになることを確認します。
+
+#[test]
+fn moves_and_drops_onto_occupied_squares_are_rejected() {
+    // 「ライオン」を、自分の「ひよこ」がいるb3に移動。
+
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (1, 4)).err(), Some(IllegalAction::OccupiedSquare(square(4))));
+
+    // 持ち駒の「ひよこ」を、敵の「ぞう」がいるc2に打つ。
+
+    assert_eq!(Game::try_next_state(&state(HAND_STATE), (4 * 3, 6)).err(), Some(IllegalAction::OccupiedSquare(square(6))));
+}
+
+// 駒のどの可能性でも移動先に移動できない場合は、Unreachableになることを確認します。
+
+#[test]
+fn unreachable_moves_are_rejected() {
+    // 「ぞう」を、a4からa3（斜めにしか動けません）。
+
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (2, 5)).err(), Some(IllegalAction::Unreachable { from: square(2), to: square(5) }));
+
+    // 「ひよこ」か「ぞう」の駒は、前にも斜めにも動けるけど、横には動けません。
+
+    let state = state("CE@b3 CGEL@c4 CGEL@b4 CGEL@a4 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0");
+
+    assert!(Game::try_next_state(&state, (4, 7)).is_ok());
+    assert!(Game::try_next_state(&state, (4, 8)).is_ok());
+    assert_eq!(Game::try_next_state(&state, (4, 3)).err(), Some(IllegalAction::Unreachable { from: square(4), to: square(3) }));
+}
+
+// 持ち駒の順番が持ち駒の数を超えている場合は、BadHandSlotになることを確認します。
+
+#[test]
+fn drops_from_missing_hand_slots_are_rejected() {
+    assert_eq!(Game::try_next_state(&state(CLASSICAL_INITIAL_STATE), (4 * 3, 3)).err(), Some(IllegalAction::BadHandSlot(0)));
+    assert_eq!(Game::try_next_state(&state(HAND_STATE), (4 * 3 + 1, 3)).err(), Some(IllegalAction::BadHandSlot(1)));
+}
+
+// ルールで打つことが禁止されている場合は、ForbiddenDropになることを確認します。
+
+#[test]
+fn forbidden_drops_are_rejected() {
+    let rules = RuleSet { chick_drop_on_last_rank: false, ..RuleSet::STANDARD };
+
+    // 「ひよこ」は、敵のエリア（c1）には打てないけど、それ以外のマス（c3）には打てます。
+
+    assert_eq!(rules.try_next_state(&state(HAND_STATE), (4 * 3, 9)).err(), Some(IllegalAction::ForbiddenDrop { hand_slot: 0, to: square(9) }));
+    assert!(rules.try_next_state(&state(HAND_STATE), (4 * 3, 3)).is_ok());
+
+    // 標準のルールなら、敵のエリアにも打てます。
+
+    assert!(Game::try_next_state(&state(HAND_STATE), (4 * 3, 9)).is_ok());
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{play, rule_set, variant};
+use proptest::{collection::vec, prelude::*};
+use quantum_animal_shogi_core::{Game, RuleSet, State};
+
+const CLASSICAL_INITIAL_STATE: &str = "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0";
+
+// 先手の「ひよこ」が後手の「ひよこ」を取ってから戻すと、取った駒も手数もハッシュ値も元に戻ることを確認します。
+
+#[test]
+fn unmake_move_restores_a_capture() {
+    let state = CLASSICAL_INITIAL_STATE.parse::<State>().unwrap();
+    let mut next_state = state;
+
+    let undo = Game::make_move(&mut next_state, (4, 7));
+
+    assert_eq!(next_state.notation().to_string(), "G@c4 L@b4 E@a4 C@b2 / C@* e@c1 l@b1 g@a1 1");
+    assert_eq!(next_state.hash, next_state.compute_hash());
+
+    Game::unmake_move(&mut next_state, undo);
+
+    assert_eq!(next_state.notation().to_string(), CLASSICAL_INITIAL_STATE);
+    assert_eq!((next_state.pieces, next_state.ownership, next_state.bit_boards, next_state.turn, next_state.hash), (state.pieces, state.ownership, state.bit_boards, state.turn, state.hash));
+}
+
+// 持ち駒を打って、「使い切り」で他の駒の可能性が外れた場合も、元に戻ることを確認します。
+
+#[test]
+fn unmake_move_restores_collapsed_pieces() {
+    let rules = RuleSet { chick_drop_on_last_rank: false, ..RuleSet::STANDARD };
+    let state = "G@c4 L@b4 E@a4 C@b2 / CG@* e@c2 l@b1 cg@a1 2".parse::<State>().unwrap();
+    let mut next_state = state;
+
+    let undo = rules.make_move(&mut next_state, (4 * 3, 9));
+
+    assert_eq!(next_state.notation().to_string(), "G@c4 L@b4 E@a4 C@b2 / G@c1 e@c2 l@b1 c@a1 3");
+
+    Game::unmake_move(&mut next_state, undo);
+
+    assert_eq!(next_state.notation().to_string(), state.notation().to_string());
+    assert_eq!(next_state.hash, state.hash);
+}
+
+proptest! {
+    // ランダムな対局で、make_move()がnext_state()と同じ結果になって、unmake_move()で元に戻ることを確認します。
+
+    #[test]
+    fn unmake_move_restores_state(variant in variant(), rules in rule_set(), choices in vec(any::<usize>(), 0..128)) {
+        play(&rules, variant.initial_state(), &choices, |state, action| {
+            let mut next_state = *state;
+            let undo = rules.make_move(&mut next_state, action);
+
+            prop_assert_eq!(next_state.notation().to_string(), rules.next_state(state, action).notation().to_string());
+
+            Game::unmake_move(&mut next_state, undo);
+
+            prop_assert_eq!(next_state.notation().to_string(), state.notation().to_string());
+            prop_assert_eq!(next_state.hash, state.hash);
+
+            Ok(())
+        })?;
+    }
+}
@@ -0,0 +1,58 @@
+use quantum_animal_shogi_core::{Game, State};
+
+// 初期状態では、由来ごとに4駒の並べ方（4! = 24通り）の組み合わせが可能世界になることを確認します。
+
+#[test]
+fn initial_state_has_all_permutations() {
+    let state = Game::initial_state();
+    let marginals = state.marginals();
+
+    assert_eq!(marginals.world_count, 24 * 24);
+    assert_eq!(state.possible_worlds().count(), 24 * 24);
+
+    for index in 0..8 {
+        assert_eq!(marginals.counts[index], [144, 144, 144, 144, 0]);
+        assert_eq!(marginals.probability(index, 3), 0.25);
+        assert_eq!(marginals.probability(index, 4), 0.0);
+    }
+
+    assert_eq!(state.feasible_pieces(), state.pieces);
+}
+
+// 可能世界は、全ての駒が確定していて、どうぶつしょうぎの局面として正しいことを確認します。
+
+#[test]
+fn possible_worlds_are_classical() {
+    let state = Game::initial_state();
+
+    for world in state.possible_worlds() {
+        let classical_state = state.to_classical(world);
+
+        assert!(classical_state.is_classical());
+        assert_eq!(classical_state.check_invariants(), Ok(()));
+        assert_eq!(classical_state.bit_boards, state.bit_boards);
+    }
+
+    // どうぶつしょうぎの初期状態の可能世界は、それ自身だけです。
+
+    let state = Game::classical_initial_state();
+
+    assert_eq!(state.possible_worlds().collect::<Vec<_>>(), vec![state.pieces]);
+    assert_eq!(state.marginals().world_count, 1);
+}
+
+// 可能性が絞り込まれた駒がある場合の、可能世界の数と周辺の数を確認します。
+
+#[test]
+fn marginals_of_narrowed_pieces() {
+    // 先手由来の駒は、ひよこ、{きりん、ぞう}、{きりん、ぞう、ライオン}、{きりん、ぞう、ライオン}です。先手由来の並べ方は、2 * 2 = 4通りです。
+
+    let state = "C@c4 GE@b4 GEL@a4 GEL@b3 / cgel@b2 cgel@c1 cgel@b1 cgel@a1 0".parse::<State>().unwrap();
+    let marginals = state.marginals();
+
+    assert_eq!(marginals.world_count, 4 * 24);
+    assert_eq!(marginals.counts[0], [4 * 24, 0, 0, 0, 0]);
+    assert_eq!(marginals.counts[1], [0, 2 * 24, 2 * 24, 0, 0]);
+    assert_eq!(marginals.counts[2], [0, 24, 24, 2 * 24, 0]);
+    assert_eq!(marginals.probability(2, 3), 0.5);
+}
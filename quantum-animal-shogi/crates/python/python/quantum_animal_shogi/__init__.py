@@ -12,8 +12,9 @@ from .quantum_animal_shogi import RawEnvironment
 class Environment(AECEnv):
     metadata = {"render_modes": ["human"], "name": "quantum-animal-shogi"}
 
-    def __init__(self, render_mode=None):
-        self.raw_env = RawEnvironment()  # メモリ効率を良くしたい場合は、本コードを参考にRawEnvironmentの使用を検討してください。呼び出しが変わるので、面倒だけど……。
+    def __init__(self, render_mode=None, variant="quantum", observation_layout="rows-v2"):
+        self.raw_env = RawEnvironment(variant)  # variantに"classical"を指定すると、（量子ではない）どうぶつしょうぎになります。メモリ効率を良くしたい場合は、本コードを参考にRawEnvironmentの使用を検討してください。呼び出しが変わるので、面倒だけど……。
+        self.observation_layout = observation_layout  # 持ち駒の行が駒の番号で決まる"rows-v2"（[20, 9]）か、CNN向けの"planes-v1"（[81, 4, 3]）か、"rows-v1"（[20, 9]。最初のバージョンと同じ）を指定してください。"rows-v1"の観測結果からは、持ち駒を打つアクションが元の環境と一致するRawEnvironmentを作成できません。
 
         self.render_mode = render_mode
         self.possible_agents = ["agent_0", "agent_1"]
@@ -27,7 +28,7 @@ class Environment(AECEnv):
 
     @lru_cache(maxsize=None)
     def observation_space(self, agent):
-        return Dict({"observation": Box(low=0, high=1, shape=[4 * 3 + 8, 5 + 2 + 2]), "action_mask": MultiBinary((4 * 3 + 8) * (4 * 3)), "turn": Discrete(256)})
+        return Dict({"observation": Box(low=0, high=1, shape=RawEnvironment.observation_shape(self.observation_layout)), "action_mask": MultiBinary((4 * 3 + 8) * (4 * 3)), "turn": Discrete(256)})
 
     def render(self):
         self.raw_env.render()
@@ -41,7 +42,7 @@ class Environment(AECEnv):
         self.agent_selection = self.agents[0]
 
         self.observations = dict([
-            (self.agents[0], self.raw_env.observe()),
+            (self.agents[0], self.raw_env.observe(self.observation_layout)),
             (self.agents[1], None)
         ])
         self.rewards = dict(map(lambda agent: (agent, 0), self.agents))
@@ -53,6 +54,9 @@ class Environment(AECEnv):
     def observe(self, agent):
         return self.observations[agent]
 
+    def record(self):
+        return self.raw_env.record()  # 棋譜を文字列で取得します。RawEnvironment.from_recordで再生できます。
+
     def step(self, action):
         if self.terminations[self.agent_selection] or self.truncations[self.agent_selection]:
             self._was_dead_step(action)
@@ -61,7 +65,7 @@ class Environment(AECEnv):
         reward = self.raw_env.step(action)
 
         if reward != 0:  # 勝敗が決定した場合です。
-            self.observations[self.agents[(self.agents.index(self.agent_selection) + 0) % 2]] = self.raw_env.observe_turned()
+            self.observations[self.agents[(self.agents.index(self.agent_selection) + 0) % 2]] = self.raw_env.observe_turned(self.observation_layout)
 
             self.rewards[self.agents[(self.agents.index(self.agent_selection) + 0) % 2]] =  reward  # noqa: E222
             self.rewards[self.agents[(self.agents.index(self.agent_selection) + 1) % 2]] = -reward if abs(reward) == 1 else reward
@@ -69,7 +73,7 @@ class Environment(AECEnv):
             self.terminations[self.agents[(self.agents.index(self.agent_selection) + 0) % 2]] = True
             self.terminations[self.agents[(self.agents.index(self.agent_selection) + 1) % 2]] = True
 
-        self.observations[self.agents[(self.agents.index(self.agent_selection) + 1) % 2]] = self.raw_env.observe()
+        self.observations[self.agents[(self.agents.index(self.agent_selection) + 1) % 2]] = self.raw_env.observe(self.observation_layout)
 
         self._accumulate_rewards()
         self.agent_selection = self.agents[(self.agents.index(self.agent_selection) + 1) % 2]
@@ -78,8 +82,8 @@ class Environment(AECEnv):
             self.render()
 
 
-def raw_environment_from_observation(observation):
-    return RawEnvironment.from_observation(observation["observation"].T, observation["turn"])
+def raw_environment_from_observation(observation, observation_layout="rows-v2"):
+    return RawEnvironment.from_observation(observation["observation"], observation["turn"], observation_layout)
 
 
 __all__ = [
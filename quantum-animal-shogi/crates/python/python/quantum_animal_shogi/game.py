@@ -52,7 +52,7 @@ async def create_agent(command, log_file):
     return Agent(await asyncio.create_subprocess_shell(command, stdin=asyncio.subprocess.PIPE, stdout=asyncio.subprocess.PIPE, stderr=log_file), log_file)
 
 
-async def play(agent_0, agent_1):
+async def play(agent_0, agent_1, record_path=None):
     result = {}
 
     agents = {
@@ -60,7 +60,7 @@ async def play(agent_0, agent_1):
         "agent_1": agent_1
     }
 
-    env = Environment(render_mode="human")
+    env = Environment(render_mode="human", observation_layout="rows-v2")  # エージェントは、観測結果からRawEnvironmentを作成するので、持ち駒の駒の番号がわかる"rows-v2"を使用します。
     env.reset()
 
     for agent in env.agent_iter():
@@ -73,6 +73,10 @@ async def play(agent_0, agent_1):
 
         env.step(await agents[agent].get_action(observation))
 
+    if record_path:
+        with open(record_path, mode="w") as f:
+            f.write(env.record())
+
     env.close()
 
     for agent in agents.values():
@@ -84,10 +88,11 @@ async def play(agent_0, agent_1):
     return result
 
 
-async def main(agent_0_command, agent_1_command):
+async def main(agent_0_command, agent_1_command, record_path):
     rewards = await play(
         await create_agent(agent_0_command, open("./agent-0.log", mode="w")),
-        await create_agent(agent_1_command, open("./agent-1.log", mode="w"))
+        await create_agent(agent_1_command, open("./agent-1.log", mode="w")),
+        record_path
     )
 
     for _, reward in sorted(rewards.items()):
@@ -100,7 +105,8 @@ if __name__ == "__main__":
     parser = ArgumentParser()
     parser.add_argument("agent_0")
     parser.add_argument("agent_1")
+    parser.add_argument("--record")
 
     args = parser.parse_args()
 
-    asyncio.run(main(args.agent_0, args.agent_1))
+    asyncio.run(main(args.agent_0, args.agent_1, args.record))
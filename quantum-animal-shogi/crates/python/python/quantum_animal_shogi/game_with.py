@@ -153,6 +153,10 @@ async def play(enemy):
                 if event.type == pygame.MOUSEBUTTONUP and event.button == 1:
                     action_item = get_action_item(event.pos)
 
+                    if action_item is not None and action_item >= 4 * 3:  # 持ち駒は詰めて表示しているので、観測結果の行（"rows-v2"なので、12 + 駒の番号）に変換します。
+                        ally_hand_items = [4 * 3 + i for i, piece in enumerate(observation["observation"][12:]) if piece[7]]
+                        action_item = ally_hand_items[action_item - 4 * 3] if action_item - 4 * 3 < len(ally_hand_items) else None
+
                     if result_0 is None:
                         candidates = list(map(
                             lambda action: action[1],
@@ -182,7 +186,7 @@ async def play(enemy):
 
     result = {}
 
-    env = Environment(render_mode="human")
+    env = Environment(render_mode="human", observation_layout="rows-v2")  # 持ち駒のクリックを観測結果の行に変換するので、持ち駒の行が駒の番号で決まる"rows-v2"を使用します。
     env.reset()
 
     for agent in env.agent_iter():
@@ -4,18 +4,16 @@ use pyo3::pymodule;
 
 #[pymodule]
 mod quantum_animal_shogi {
-    use std::iter::empty;
-
-    use itertools::Itertools;
-    use nalgebra::SMatrix;
-    use ndarray::{Array1, Array2};
-    use numpy::{IntoPyArray, PyReadonlyArray2};
-    use pyo3::{Bound, PyAny, PyResult, Python, pyclass, pymethods, types::{PyAnyMethods, PyDict}};
-    use quantum_animal_shogi_core::{Game, State, bits};
+    use ndarray::{Array1, Array2, ArrayD, ArrayView1, IxDyn};
+    use numpy::{Element, IntoPyArray, PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArrayDyn, PyUntypedArrayMethods};
+    use pyo3::{Bound, PyAny, PyResult, Python, exceptions::PyValueError, pyclass, pymethods, types::{PyAnyMethods, PyDict}};
+    use quantum_animal_shogi_core::{Action, Game, GameHistory, GameRecord, ObservationLayout, Outcome, State, UnknownLayout, Variant};
 
     // 観測します。RustのStateのままでも良いのですけど、Pythonで扱いやすい（と思われる）形に変換しておきます。
 
-    fn observation<'py>(state: &State, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+    fn observation<'py>(state: &State, layout: &str, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let layout = parse_observation_layout(layout)?;
+
         // 観測結果そのものは"observation"に入れ、合法手を観測結果の"action_mask"に入れるのがPettingZooのおすすめみたいなので、Dictを作成します。
 
         let result = PyDict::new(py);  // PyDictはmutでなくても更新できちゃいます。。。
@@ -23,52 +21,9 @@ mod quantum_animal_shogi {
         result.set_item(
             "observation",
             {
-                // "observation"はNumPy配列（Dictではない）がPettingZooのおすすめみたいなので、NumPy配列を作成します。
-
-                let mut result = Array2::<f32>::zeros((4 * 3 + 8, 5 + 2 + 2));  // [行(4)×列（3）＋持ち駒（自分と敵合わせて8）、駒種（ひよこ、きりん、ぞう、ライオン、にわとり）＋由来（先手、後手））＋所有者（自分、敵）]。
-
-                // 盤面。
-
-                for index in 0..8_usize {
-                    let bit_board = state.bit_boards[index];
-
-                    if bit_board == 0 {
-                        continue;
-                    }
-
-                    let i = (4 * 3 - 1 - bit_board.trailing_zeros()) as usize;
-
-                    for piece_bit in bits(state.pieces[index]) {
-                        result[[i, piece_bit]] = 1.0;
-                    }
-
-                    result[[i, 5 + if (0..4).contains(&index) { 0 } else { 1 }]] = 1.0;
-                    result[[i, 5 + 2 + if state.ownership & 1 << index != 0 { 0 } else { 1 }]] = 1.0;
-                }
-
-                // 自分の持ち駒。
-
-                for (i, index) in bits(state.ownership).filter(|index| state.bit_boards[*index] == 0).enumerate().map(|(i, index)| (4 * 3 + i, index)) {
-                    for piece_bit in bits(state.pieces[index]) {
-                        result[[i, piece_bit]] = 1.0;
-                    }
-
-                    result[[i, 5 + if (0..4).contains(&index) { 0 } else { 1 }]] = 1.0;
-                    result[[i, 5 + 2 + 0]] = 1.0;
-                }
-
-                // 敵の持ち駒。
-
-                for (i, index) in bits(!state.ownership).filter(|index| state.bit_boards[*index] == 0).enumerate().map(|(i, index)| (4 * 3 + 8 - 1 - i, index)) {
-                    for piece_bit in bits(state.pieces[index]) {
-                        result[[i, piece_bit]] = 1.0;
-                    }
+                // "observation"はNumPy配列（Dictではない）がPettingZooのおすすめみたいなので、NumPy配列を作成します。レイアウトはObservationLayoutを参照してください。
 
-                    result[[i, 5 + if (0..4).contains(&index) { 0 } else { 1 }]] = 1.0;
-                    result[[i, 5 + 2 + 1]] = 1.0;
-                }
-
-                result.into_pyarray(py)
+                ArrayD::from_shape_vec(IxDyn(layout.shape()), layout.encode(state)).unwrap().into_pyarray(py)
             }
         )?;
 
@@ -77,22 +32,15 @@ mod quantum_animal_shogi {
         result.set_item(
             "action_mask",
             {
-                // "action_mask"は1次元のMultiBinaryがPettingZooのおすすめみたいなので、選択可能なアクションのインデックスをTrueにしたNumPy配列を作成します。1次元のMultiBinaryにするために、アクションは(u8, u8)ではなく、u16にします。で、action.0 << 4 | action.1だと膨大な数の配列になってしまうので、action.0 * 12 + action.1にします。
-
-                let mut result = Array1::<i8>::zeros((4 * 3 + 8) * 4 * 3);
+                // "action_mask"は1次元のMultiBinaryがPettingZooのおすすめみたいなので、選択可能なアクションのインデックスをTrueにしたNumPy配列を作成します。1次元のMultiBinaryにするために、アクションは(u8, u8)ではなく、Action::index()のインデックスにします。
+                // 持ち駒を打つアクションは、持ち駒が増えたり減ったりしても番号が変わらないよう、安定した表現（State::to_stable_action()）にします。
 
-                for action in Game::legal_actions(&state) {
-                    // Python側の座標系（Rust側では0は盤面の右下ですが、Python側では左上）に合うように、アクションを変更します。
+                let mut result = Array1::<i8>::zeros(Action::INDEX_COUNT);
 
-                    let action = {
-                        if action.0 < 4 * 3 {
-                            (12 - 1 - action.0, 12 - 1 - action.1)
-                        } else {
-                            (action.0, 12 - 1 - action.1)
-                        }
-                    };
+                for action in Game::legal_actions(state) {
+                    let stable_action = state.to_stable_action(action).ok_or_else(|| PyValueError::new_err(format!("invalid action: {:?}", action)))?;
 
-                    result[(action.0 as usize) * (4 * 3) + (action.1 as usize)] = 1
+                    result[Action::try_from(stable_action).map_err(|error| PyValueError::new_err(error.to_string()))?.index()] = 1
                 }
 
                 result.into_pyarray(py)
@@ -109,9 +57,36 @@ mod quantum_animal_shogi {
     // PettingZooのAECEnvを委譲で作成可能にするためのクラスです。
 
     #[pyclass(from_py_object)]
-    #[derive(Clone, Copy)]
+    #[derive(Clone)]
     struct RawEnvironment {
-        state: State
+        history: GameHistory,  // 千日手を判定できるよう、局面の履歴も保持します。
+        variant: Variant       // ゲームの種類（resetで使用します）
+    }
+
+    // Python側のアクションのインデックスの配列（合法手のマスクやポリシー）を、左右反転します。
+
+    fn mirror_actions<T: Element + Copy>(vector: ArrayView1<T>) -> PyResult<Array1<T>> {
+        if vector.len() != Action::INDEX_COUNT {
+            return Err(PyValueError::new_err(format!("expected {} values, got {}", Action::INDEX_COUNT, vector.len())));
+        }
+
+        Ok(Array1::from_shape_fn(Action::INDEX_COUNT, |index| vector[Action::mirrored_index(index).unwrap()]))
+    }
+
+    // 観測結果のレイアウトを、文字列から取得します。
+
+    fn parse_observation_layout(layout: &str) -> PyResult<ObservationLayout> {
+        layout.parse().map_err(|error: UnknownLayout| PyValueError::new_err(error.to_string()))
+    }
+
+    // ゲームの種類を、文字列から取得します。
+
+    fn parse_variant(variant: &str) -> PyResult<Variant> {
+        match variant {
+            "quantum"   => Ok(Variant::Quantum),
+            "classical" => Ok(Variant::Classical),
+            _           => Err(PyValueError::new_err(format!("unknown variant: {}", variant)))
+        }
     }
 
     #[pymethods]
@@ -119,55 +94,94 @@ mod quantum_animal_shogi {
         // コンストラクタです。
 
         #[new]
-        fn new() -> Self {
-            Self {
-                state: Game::initial_state()
-            }
+        #[pyo3(signature = (variant = "quantum"))]
+        fn new(variant: &str) -> PyResult<Self> {
+            let variant = parse_variant(variant)?;
+
+            Ok(
+                Self {
+                    history: GameHistory::new(variant.initial_state()),
+                    variant
+                }
+            )
         }
 
-        // 観測結果からRawEnvironmentを作成します。
+        // 観測結果からRawEnvironmentを作成します。layoutには、観測した時と同じレイアウトを指定してください。"rows-v1"は持ち駒の駒の番号がわからないので、持ち駒を打つアクションが元の環境と一致しません。"rows-v2"か"planes-v1"を使用してください。
 
         #[staticmethod]
-        fn from_observation(observation: PyReadonlyArray2<f32>, turn: u16) -> Self {
-            let observation: SMatrix<f32, 9, 20>  = SMatrix::from_column_slice(observation.as_slice().unwrap());
-
-            let indices = empty()
-                .chain(observation.column_iter().enumerate().filter_map(|(index, column)| if column[5] == 1.0 { Some(index) } else { None }))
-                .chain(observation.column_iter().enumerate().filter_map(|(index, column)| if column[6] == 1.0 { Some(index) } else { None }))
-                .collect_array::<8>()
-                .unwrap();
-
-            let pieces = indices
-                .iter()
-                .map(|index| observation.column(*index))
-                .map(|column| (column[0] as u8) << 0 | (column[1] as u8) << 1 | (column[2] as u8) << 2 | (column[3] as u8) << 3 | (column[4] as u8) << 4)
-                .collect_array::<8>()
-                .unwrap();
-
-            let ownership = indices
-                .iter()
-                .enumerate()
-                .filter_map(|(i, index)| if observation.column(*index)[7] == 1.0 { Some(i) } else { None })
-                .fold(0, |acc, i| acc | 1 << i);
-
-            let bit_boards = indices
-                .iter()
-                .map(|index| if *index < 12 { 1 << (12 - 1 - *index) } else { 0 })
-                .collect_array::<8>()
-                .unwrap();
+        fn from_observation(observation: PyReadonlyArrayDyn<f32>, turn: u16, layout: &str) -> PyResult<Self> {
+            let layout = parse_observation_layout(layout)?;
 
-            Self {
-                state: State { pieces, ownership, bit_boards, turn }
+            if observation.shape() != layout.shape() {
+                return Err(PyValueError::new_err(format!("invalid observation: expected shape {:?}, got {:?}", layout.shape(), observation.shape())));
             }
+
+            let state = layout
+                .decode(&observation.as_array().iter().copied().collect::<Vec<_>>(), turn)  // 転置されている場合もあるので、論理的な順序で取得します。
+                .map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+            Ok(
+                Self {
+                    history: GameHistory::new(state),  // 観測結果には履歴が含まれないので、千日手は観測以降の局面だけで判定します。
+                    variant: Variant::Quantum
+                }
+            )
+        }
+
+        // 観測結果の形を取得します。
+
+        #[staticmethod]
+        fn observation_shape(layout: &str) -> PyResult<Vec<usize>> {
+            Ok(parse_observation_layout(layout)?.shape().to_vec())
+        }
+
+        // 文字列表記からRawEnvironmentを作成します。
+
+        #[staticmethod]
+        fn from_notation(notation: &str) -> PyResult<Self> {
+            let state = notation.parse::<State>().map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+            Ok(
+                Self {
+                    history: GameHistory::new(state),
+                    variant: if state.is_classical() { Variant::Classical } else { Variant::Quantum }
+                }
+            )
+        }
+
+        // 状態の文字列表記を取得します。
+
+        fn notation(&self) -> String {
+            self.history.state().notation().to_string()
+        }
+
+        // 棋譜を再生して、RawEnvironmentを作成します。
+
+        #[staticmethod]
+        fn from_record(record: &str) -> PyResult<Self> {
+            let record = record.parse::<GameRecord>().map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+            Ok(
+                Self {
+                    history: record.replay().map_err(|error| PyValueError::new_err(error.to_string()))?,
+                    variant: if record.initial_state.is_classical() { Variant::Classical } else { Variant::Quantum }
+                }
+            )
+        }
+
+        // 棋譜を取得します。
+
+        fn record(&self) -> String {
+            self.history.record().to_string()
         }
 
         // 状態を描画します。
 
         fn render(&self) {
-            let state = if self.state.turn % 2 == 0 {
-                self.state
+            let state = if self.history.state().turn.is_multiple_of(2) {
+                *self.history.state()
             } else {
-                let mut result = self.state.clone();
+                let mut result = *self.history.state();
 
                 result.ownership = !result.ownership;
                 result.bit_boards = result.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
@@ -175,28 +189,28 @@ mod quantum_animal_shogi {
                 result
             };
 
-            println!("{}", state.to_string());
+            println!("{}", state);
         }
 
         // 環境をリセットします。
 
         fn reset(&mut self) {
-            self.state = Game::initial_state();
+            self.history = GameHistory::new(self.variant.initial_state());
         }
 
-        // 観測を実施します。
+        // 観測を実施します。layoutには、"rows-v1"か"rows-v2"か"planes-v1"を指定してください（ObservationLayoutを参照）。
 
-        fn observe<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
-            observation(&self.state, py)
+        fn observe<'py>(&self, layout: &str, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+            observation(self.history.state(), layout, py)
         }
 
         // 勝敗が決した後に盤面を観測できるよう、回転させた状態での観測を実施します。
 
-        fn observe_turned<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        fn observe_turned<'py>(&mut self, layout: &str, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
             // 盤面を回転した状態を取得します。
 
             let state = {
-                let mut result = self.state.clone();
+                let mut result = *self.history.state();
 
                 result.ownership = !result.ownership;
                 result.bit_boards = result.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
@@ -206,122 +220,142 @@ mod quantum_animal_shogi {
 
             // 観測結果を取得し、リターンします。
 
-            observation(&state, py)
+            observation(&state, layout, py)
         }
 
-        // 1ステップ進め、報酬を返します。
-
-        fn step(&mut self, action: i32) -> f32 {
-            // Python側の座標系（Rust側では0は盤面の右下ですが、Python側では左上）に合うように、アクションを変更します。
-
-            let action = {
-                let result = ((action / (4 * 3)) as u8, (action % (4 * 3)) as u8);
-
-                if result.0 < 4 * 3 {
-                    (12 - 1 - result.0, 12 - 1 - result.1)
-                } else {
-                    (result.0, 12 - 1 - result.1)
-                }
-            };
+        // 1ステップ進め、報酬を返します。勝敗が決した後は、ValueErrorになります。
 
-            // 合法手であることをチェックします。
+        fn step(&mut self, action: i32) -> PyResult<f32> {
+            // 勝敗が決した後のアクションは、棋譜を再生できなくなる（pickleから復元できなくなる）ので、受け付けません。
 
-            if !Game::legal_actions(&self.state).contains(&action) {
-                return -1.0;  // 不正なアクションは反則負けとします。
+            if self.history.outcome().is_over() {
+                return Err(PyValueError::new_err("the game is already over"));
             }
 
-            // 状態を遷移します。
+            // Python側のインデックスを、アクションに変換します。
 
-            self.state = Game::next_state(&self.state, action);
+            let Some(action) = usize::try_from(action).ok().and_then(Action::from_index).and_then(|action| self.history.state().from_stable_action(action.into())) else {
+                return Ok(-1.0);  // 不正なアクションは反則負けとします。
+            };
 
-            if Game::won(&self.state) {
-                return -1.0;  // 次の状態での手番は敵なので、敵が勝ったら自分の負けになります。
-            }
+            // 合法手であることをチェックしながら、状態を遷移します。
 
-            if Game::lost(&self.state) {
-                return  1.0;  // 次の状態での手番は敵なので、敵が負けたら自分の勝ちになります。
+            if self.history.try_play(action).is_err() {
+                return Ok(-1.0);  // 不正なアクションは反則負けとします。
             }
 
-            if Game::draw(&self.state) {
-                return -0.5;  // 千日手を避けたいので、引き分けは半分負けとして扱います。
-            }
+            // 次の状態での手番は敵なので、アクションを実行した側（自分）から見た結果に変換します。
 
-            0.0
+            Ok(
+                match self.history.outcome().reversed() {
+                    Outcome::Win(_)  =>  1.0,
+                    Outcome::Loss(_) => -1.0,
+                    Outcome::Draw(_) => -0.5,  // 千日手を避けたいので、引き分けは半分負けとして扱います。
+                    Outcome::Ongoing =>  0.0
+                }
+            )
         }
 
         // 勝ったかどうかを取得します。
 
         fn won(&self) -> bool {
-            Game::won(&self.state)
+            matches!(self.history.outcome(), Outcome::Win(_))
         }
 
         // 負けたかどうかを取得します。
 
         fn lost(&self) -> bool {
-            Game::lost(&self.state)
+            matches!(self.history.outcome(), Outcome::Loss(_))
         }
 
         // 引き分けかどうかを取得します。
 
         fn draw(&self) -> bool {
-            Game::draw(&self.state)
+            matches!(self.history.outcome(), Outcome::Draw(_))
+        }
+
+        // 手番側から見たゲームの結果を、"ongoing"、"win by try"、"loss by catch"、"draw by repetition"のような文字列で取得します。対局ログ等で、勝敗が決した理由を記録するのに使用してください。
+
+        fn outcome(&self) -> String {
+            self.history.outcome().to_string()
+        }
+
+        // 駒の種類ごとの確率（全ての可能世界が同じ確率だとした場合）を取得します。[駒（State::piecesの順）、駒の種類（ひよこ、きりん、ぞう、ライオン、にわとり）]のNumPy配列です。
+
+        fn probabilities<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+            let marginals = self.history.state().marginals();
+
+            Array2::from_shape_fn((8, 5), |(index, piece_bit)| marginals.probability(index, piece_bit) as f32).into_pyarray(py)
+        }
+
+        // 盤面を左右反転したRawEnvironmentを作成します。学習データの水増しに使用してください。
+
+        fn mirrored(&self) -> Self {
+            Self {
+                history: self.history.mirrored(),
+                variant: self.variant
+            }
+        }
+
+        // 合法手のマスク（"action_mask"）を、左右反転したRawEnvironmentのものに変換します。
+
+        #[staticmethod]
+        fn mirror_action_mask<'py>(action_mask: PyReadonlyArray1<'py, i8>, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<i8>>> {
+            Ok(mirror_actions(action_mask.as_array())?.into_pyarray(py))
+        }
+
+        // ポリシー（アクションごとの確率）を、左右反転したRawEnvironmentのものに変換します。
+
+        #[staticmethod]
+        fn mirror_policy<'py>(policy: PyReadonlyArray1<'py, f32>, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<f32>>> {
+            Ok(mirror_actions(policy.as_array())?.into_pyarray(py))
+        }
+
+        // 局面のハッシュ値（Zobristハッシュ）を取得します。置換表等のキーとして使用してください。衝突する場合があるので、衝突が許されない場合はcompact_key()を使用してください。
+
+        fn hash(&self) -> u64 {
+            self.history.state().hash
+        }
+
+        // 局面のキー（State::compact_key()）を取得します。衝突しないので、MCTSの木のキー等に使用してください。手番は含まないので、必要ならturn()と組み合わせてください。
+
+        fn compact_key(&self) -> u128 {
+            self.history.state().compact_key()
+        }
+
+        // 手数を取得します。
+
+        fn turn(&self) -> u16 {
+            self.history.state().turn
         }
 
         // Pythonで状態として保存でき量にするために、copyとdeepcopy、piekleに対応させます。
 
         fn __copy__(&self) -> Self {
-            *self
+            self.clone()
         }
 
         fn __deepcopy__(&self) -> Self {
-            *self
+            self.clone()
         }
 
         fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
             let result = PyDict::new(py);
 
-            result.set_item("pieces", pyo3::types::PyBytes::new(py, &self.state.pieces))?;
-            result.set_item("ownership", self.state.ownership)?;
-            result.set_item("bit_boards", self.state.bit_boards.to_vec())?;
-            result.set_item("state", self.state.turn)?;
+            result.set_item("record", self.history.record().to_string())?;  // 千日手を判定できるよう、棋譜として保存します。
 
             Ok(result)
         }
 
         #[staticmethod]
         fn __setstate__(state: &Bound<'_, PyAny>) -> PyResult<Self> {
-            let pieces = {
-                let mut result = [0_u8; 8];
-                result.copy_from_slice(&state.get_item("pieces")?.extract::<Vec<_>>()?);
-                result
-            };
-
-            let ownership: u8 = state.get_item("ownership")?.extract()?;
-
-            let bit_boards = {
-                let mut result = [0_u16; 8];
-                result.copy_from_slice(&state.get_item("bit_boards")?.extract::<Vec<_>>()?);
-                result
-            };
-
-            let turn = state.get_item("turn")?.extract::<u16>()?;
-
-            Ok(
-                Self {
-                    state: State {
-                        pieces,
-                        ownership,
-                        bit_boards,
-                        turn
-                    }
-                }
-            )
+            Self::from_record(&state.get_item("record")?.extract::<String>()?)
         }
 
         // デバッグ用に、盤面を文字列化します。
 
         fn __str__(&self) -> String {
-            self.state.to_string()
+            self.history.state().to_string()
         }
     }
 }
@@ -0,0 +1,320 @@
+use std::{collections::BTreeSet, fs::{self, create_dir_all, remove_file}, io::{self, ErrorKind}, path::{Path, PathBuf}};
+
+use quantum_animal_shogi_core::{Game, State};
+use rayon::prelude::*;
+
+use crate::{Progress, external::{KeyReader, KeyWriter, MergedKeys, write_run}, is_terminal_state, progress::Ticker};
+
+// 初期状態から到達可能な局面を、幅優先探索で数えます。局面は正規化したキー（State::canonical_key()）で区別するので、駒のインデックスが入れ替わっただけの局面や、左右反転した局面は同じ局面として数えます。終端局面（is_terminal_state()）は数えますけど、その先は探索しません。
+
+// ディスクを使用して数える場合に、一度に展開するフロンティアの局面の数です。
+
+const CHUNK_SIZE: usize = 1 << 16;
+
+// マージの途中で、進捗を報告するか確認する間隔（キーの数）です。
+
+const TICK_KEYS: u64 = 1 << 16;
+
+// 探索の深さごとの統計です。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub depth: u32,     // 深さ（初期状態が0）
+    pub frontier: u64,  // この深さで初めて到達した局面の数
+    pub visited: u64    // この深さまでに到達した局面の数
+}
+
+// メモリ上で数えます。どうぶつしょうぎなら、数GBのメモリで数えられます。max_depthを指定すると、その深さで探索を打ち切ります。
+
+pub fn count_in_memory(state: &State, max_depth: Option<u32>, mut on_level: impl FnMut(&Level)) -> u64 {
+    let mut visited = BTreeSet::from([state.canonical_key()]);
+    let mut frontier = vec![*state];
+
+    on_level(&Level { depth: 0, frontier: 1, visited: 1 });
+
+    for depth in 1..=max_depth.unwrap_or(u32::MAX) {
+        let mut next_frontier = Vec::new();
+
+        for state in frontier.iter().filter(|state| !is_terminal_state(state)) {
+            for action in Game::legal_actions(state) {
+                let next_state = Game::next_state(state, action);
+
+                if visited.insert(next_state.canonical_key()) {
+                    next_frontier.push(next_state);
+                }
+            }
+        }
+
+        if next_frontier.is_empty() {
+            break;
+        }
+
+        frontier = next_frontier;
+
+        on_level(&Level { depth, frontier: frontier.len() as u64, visited: visited.len() as u64 });
+    }
+
+    visited.len() as u64
+}
+
+// ディスクを使用して数えます（外部メモリの幅優先探索）。深さごとに、以下を実行します。
+//
+// 1. 前の深さで初めて到達した局面（フロンティア）のファイルを読み込んで、並列に展開した次の局面のキーをメモリに貯めます。run_capacity個貯まったら、並列にソートしてランのファイルに書き込みます。
+// 2. ランのファイルをマージして、それまでに到達した局面（訪問済み）のファイルにないキーを、次のフロンティアのファイルに書き込みます。
+// 3. 訪問済みのファイルと次のフロンティアのファイルをマージして、新しい訪問済みのファイルにします。
+// 4. チェックポイントのファイル（checkpoint）に、深さと局面の数を書き込みます。
+//
+// メモリの使用量はrun_capacity * 16バイト程度で、ディスクは訪問済みの局面の数 * 16バイトの2〜3倍程度を使用します。終了後、work_dirには最後の深さの訪問済みのファイル（visited-<深さ>.keys）が残ります。
+//
+// 途中で止まっても、resume()でチェックポイントの深さから再開できます（何時間もかかるので）。深さの途中でも、展開とマージの進捗を一定の時間ごとに報告します。
+
+pub struct ExternalEnumerator {
+    pub work_dir: PathBuf,   // 作業用のディレクトリ
+    pub run_capacity: usize  // メモリに貯めるキーの数
+}
+
+impl ExternalEnumerator {
+    // 数えます。max_depthを指定すると、その深さで探索を打ち切ります。
+
+    pub fn count(&self, state: &State, max_depth: Option<u32>, on_progress: impl FnMut(&Progress)) -> io::Result<u64> {
+        Ok(self.enumerate(state, max_depth, is_terminal_state, on_progress)?.visited)
+    }
+
+    // チェックポイントから、数えるのを再開します。チェックポイントが別の初期状態のものの場合は、エラーになります。
+
+    pub fn resume(&self, state: &State, max_depth: Option<u32>, on_progress: impl FnMut(&Progress)) -> io::Result<u64> {
+        Ok(self.resume_enumeration(state, max_depth, is_terminal_state, on_progress)?.visited)
+    }
+
+    // 到達可能な局面を列挙して、最後の深さの統計を返します。is_leafがtrueを返す局面は、その先を探索しません。列挙した局面のキーは、昇順にvisited_path(最後の深さ)のファイルに残ります。
+
+    pub(crate) fn enumerate(&self, state: &State, max_depth: Option<u32>, is_leaf: impl Fn(&State) -> bool + Sync, mut on_progress: impl FnMut(&Progress)) -> io::Result<Level> {
+        create_dir_all(&self.work_dir)?;
+
+        // 初期状態を、フロンティアと訪問済みのファイルに書き込みます。
+
+        for path in [self.frontier_path(0), self.visited_path(0)] {
+            let mut writer = KeyWriter::create(&path)?;
+
+            writer.write(state.canonical_key())?;
+            writer.finish()?;
+        }
+
+        let level = Level { depth: 0, frontier: 1, visited: 1 };
+
+        Checkpoint { root: state.canonical_key(), level }.write(&self.checkpoint_path())?;
+
+        on_progress(&Progress::Level(level));
+
+        self.expand(state, level, max_depth, is_leaf, on_progress)
+    }
+
+    // チェックポイントから、列挙を再開します。
+
+    pub(crate) fn resume_enumeration(&self, state: &State, max_depth: Option<u32>, is_leaf: impl Fn(&State) -> bool + Sync, on_progress: impl FnMut(&Progress)) -> io::Result<Level> {
+        let checkpoint = Checkpoint::read(&self.checkpoint_path())?;
+
+        if checkpoint.root != state.canonical_key() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "checkpoint was made for another initial state"));
+        }
+
+        if checkpoint.level.frontier == 0 {
+            return Ok(checkpoint.level);
+        }
+
+        self.expand(state, checkpoint.level, max_depth, is_leaf, on_progress)
+    }
+
+    // levelの深さのフロンティアから、探索を続けます。
+
+    fn expand(&self, state: &State, level: Level, max_depth: Option<u32>, is_leaf: impl Fn(&State) -> bool + Sync, mut on_progress: impl FnMut(&Progress)) -> io::Result<Level> {
+        let mut last_level = level;
+        let mut ticker = Ticker::new();
+
+        for depth in level.depth + 1..=max_depth.unwrap_or(u32::MAX) {
+            // 次の局面のキーを、ランのファイルに書き込みます。
+
+            let mut run_paths = Vec::new();
+            let mut keys = Vec::with_capacity(self.run_capacity);
+
+            let mut frontier_keys = KeyReader::open(&self.frontier_path(depth - 1))?.peekable();
+            let mut expanded = 0;
+
+            while frontier_keys.peek().is_some() {
+                // フロンティアをチャンクごとに読み込んで、並列に展開します。
+
+                let chunk = frontier_keys.by_ref().take(CHUNK_SIZE).collect::<io::Result<Vec<_>>>()?;
+                let turn = state.turn.wrapping_add(depth as u16 - 1);
+
+                let next_keys = chunk
+                    .into_par_iter()
+                    .map(|key| {
+                        let state = State::from_compact_key(key, turn).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+                        if is_leaf(&state) {
+                            return Ok(Vec::new());
+                        }
+
+                        Ok(Game::legal_actions(&state).map(|action| Game::next_state(&state, action).canonical_key()).collect())
+                    })
+                    .collect::<io::Result<Vec<Vec<_>>>>()?;
+
+                expanded += next_keys.len() as u64;
+
+                for next_key in next_keys.into_iter().flatten() {
+                    keys.push(next_key);
+
+                    if keys.len() >= self.run_capacity {
+                        run_paths.push(self.run_path(depth, run_paths.len()));
+                        write_run(run_paths.last().unwrap(), &mut keys)?;
+                    }
+                }
+
+                if ticker.tick() {
+                    on_progress(&Progress::Expanding { depth, expanded, frontier: last_level.frontier });
+                }
+            }
+
+            if !keys.is_empty() {
+                run_paths.push(self.run_path(depth, run_paths.len()));
+                write_run(run_paths.last().unwrap(), &mut keys)?;
+            }
+
+            // ランをマージして、訪問済みではないキーを次のフロンティアにします。
+
+            let frontier = {
+                let mut writer = KeyWriter::create(&self.frontier_path(depth))?;
+                let mut visited_keys = KeyReader::open(&self.visited_path(depth - 1))?;
+                let mut visited_key = visited_keys.next().transpose()?;
+                let mut merged = 0_u64;
+
+                for key in MergedKeys::new(run_paths.iter().map(|path| KeyReader::open(path)).collect::<io::Result<Vec<_>>>()?)? {
+                    let key = key?;
+
+                    merged += 1;
+
+                    if merged.is_multiple_of(TICK_KEYS) && ticker.tick() {
+                        on_progress(&Progress::Merging { depth, merged });
+                    }
+
+                    while visited_key.is_some_and(|visited_key| visited_key < key) {
+                        visited_key = visited_keys.next().transpose()?;
+                    }
+
+                    if visited_key == Some(key) {
+                        continue;
+                    }
+
+                    writer.write(key)?;
+                }
+
+                writer.finish()?
+            };
+
+            for path in &run_paths {
+                remove_file(path)?;
+            }
+
+            // 訪問済みのファイルを更新します。
+
+            let visited = {
+                let mut writer = KeyWriter::create(&self.visited_path(depth))?;
+                let mut merged = 0_u64;
+
+                for key in MergedKeys::new([KeyReader::open(&self.visited_path(depth - 1))?, KeyReader::open(&self.frontier_path(depth))?])? {
+                    writer.write(key?)?;
+
+                    merged += 1;
+
+                    if merged.is_multiple_of(TICK_KEYS) && ticker.tick() {
+                        on_progress(&Progress::Merging { depth, merged });
+                    }
+                }
+
+                writer.finish()?
+            };
+
+            // チェックポイントを書き込んでから、前の深さのファイルを削除します。
+
+            last_level = Level { depth, frontier, visited };
+
+            Checkpoint { root: state.canonical_key(), level: last_level }.write(&self.checkpoint_path())?;
+
+            remove_file(self.visited_path(depth - 1))?;
+            remove_file(self.frontier_path(depth - 1))?;
+
+            if frontier == 0 {
+                remove_file(self.frontier_path(depth))?;
+                break;
+            }
+
+            on_progress(&Progress::Level(last_level));
+        }
+
+        Ok(last_level)
+    }
+
+    // チェックポイントのファイルのパスを取得します。
+
+    fn checkpoint_path(&self) -> PathBuf {
+        self.work_dir.join("checkpoint")
+    }
+
+    // フロンティアのファイルのパスを取得します。
+
+    fn frontier_path(&self, depth: u32) -> PathBuf {
+        self.work_dir.join(format!("frontier-{}.keys", depth))
+    }
+
+    // 訪問済みのファイルのパスを取得します。
+
+    pub(crate) fn visited_path(&self, depth: u32) -> PathBuf {
+        self.work_dir.join(format!("visited-{}.keys", depth))
+    }
+
+    // ランのファイルのパスを取得します。
+
+    fn run_path(&self, depth: u32, i: usize) -> PathBuf {
+        self.work_dir.join(format!("run-{}-{}.keys", depth, i))
+    }
+}
+
+// チェックポイントです。ファイルには、初期状態のキー（16進数）と深さ、フロンティアの局面の数、訪問済みの局面の数を、空白で区切って1行で書きます。
+
+struct Checkpoint {
+    root: u128,
+    level: Level
+}
+
+impl Checkpoint {
+    // ファイルに書き込みます。途中で止まっても壊れないよう、一時ファイルに書き込んでから名前を変更します。
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let temporary_path = path.with_extension("tmp");
+
+        fs::write(&temporary_path, format!("{:032x} {} {} {}\n", self.root, self.level.depth, self.level.frontier, self.level.visited))?;
+        fs::rename(&temporary_path, path)
+    }
+
+    // ファイルから読み込みます。
+
+    fn read(path: &Path) -> io::Result<Checkpoint> {
+        let invalid = || io::Error::new(ErrorKind::InvalidData, "invalid checkpoint");
+
+        let text = fs::read_to_string(path)?;
+        let fields = text.split_whitespace().collect::<Vec<_>>();
+
+        let [root, depth, frontier, visited] = fields[..] else {
+            return Err(invalid());
+        };
+
+        Ok(Checkpoint {
+            root: u128::from_str_radix(root, 16).map_err(|_| invalid())?,
+            level: Level {
+                depth: depth.parse().map_err(|_| invalid())?,
+                frontier: frontier.parse().map_err(|_| invalid())?,
+                visited: visited.parse().map_err(|_| invalid())?
+            }
+        })
+    }
+}
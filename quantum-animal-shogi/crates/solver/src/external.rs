@@ -0,0 +1,213 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fs::{File, OpenOptions}, io::{self, BufReader, BufWriter, ErrorKind, Read, Write}, path::Path};
+
+use memmap2::{Mmap, MmapMut};
+use rayon::slice::ParallelSliceMut;
+
+// キー（State::canonical_key()）のファイルです。メモリに収まらない数のキーを扱えるよう、昇順に並べたキーをファイルに保存して、ファイル同士をマージします（外部メモリのソート）。
+//
+// ファイルの形式は、キーをリトル・エンディアンの16バイトで並べただけです。
+
+const KEY_SIZE: usize = 16;
+const BUFFER_SIZE: usize = 1 << 20;
+
+// キーをファイルに書き込みます。
+
+pub struct KeyWriter {
+    writer: BufWriter<File>,
+    count: u64
+}
+
+impl KeyWriter {
+    // ファイルを作成します。
+
+    pub fn create(path: &Path) -> io::Result<KeyWriter> {
+        Ok(KeyWriter { writer: BufWriter::with_capacity(BUFFER_SIZE, File::create(path)?), count: 0 })
+    }
+
+    // キーを書き込みます。
+
+    pub fn write(&mut self, key: u128) -> io::Result<()> {
+        self.count += 1;
+        self.writer.write_all(&key.to_le_bytes())
+    }
+
+    // ファイルを閉じて、書き込んだキーの数を返します。
+
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.writer.flush()?;
+
+        Ok(self.count)
+    }
+}
+
+// キーをファイルから読み込みます。
+
+pub struct KeyReader {
+    reader: BufReader<File>
+}
+
+impl KeyReader {
+    // ファイルを開きます。
+
+    pub fn open(path: &Path) -> io::Result<KeyReader> {
+        Ok(KeyReader { reader: BufReader::with_capacity(BUFFER_SIZE, File::open(path)?) })
+    }
+}
+
+impl Iterator for KeyReader {
+    type Item = io::Result<u128>;
+
+    fn next(&mut self) -> Option<io::Result<u128>> {
+        let mut bytes = [0; KEY_SIZE];
+        let mut length = 0;
+
+        // キーの途中でファイルが終わった場合は、ファイルの終わりではなく壊れたファイルとします（MappedKeys::open()と同じ）。
+
+        while length < KEY_SIZE {
+            match self.reader.read(&mut bytes[length..]) {
+                Ok(0) if length == 0                                 => return None,
+                Ok(0)                                                => return Some(Err(io::Error::new(ErrorKind::InvalidData, "truncated key file"))),
+                Ok(count)                                            => length += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error)                                           => return Some(Err(error))
+            }
+        }
+
+        Some(Ok(u128::from_le_bytes(bytes)))
+    }
+}
+
+// 昇順に並んだキーの列をマージして、重複を除いた昇順のキーの列にします。
+
+pub struct MergedKeys<I: Iterator<Item = io::Result<u128>>> {
+    sources: Vec<I>,
+    heap: BinaryHeap<Reverse<(u128, usize)>>,
+    last: Option<u128>
+}
+
+impl<I: Iterator<Item = io::Result<u128>>> MergedKeys<I> {
+    // コンストラクタです。
+
+    pub fn new(sources: impl IntoIterator<Item = I>) -> io::Result<MergedKeys<I>> {
+        let mut result = MergedKeys { sources: sources.into_iter().collect(), heap: BinaryHeap::new(), last: None };
+
+        for i in 0..result.sources.len() {
+            result.advance(i)?;
+        }
+
+        Ok(result)
+    }
+
+    // i番目の列から次のキーを読み込んで、ヒープに入れます。
+
+    fn advance(&mut self, i: usize) -> io::Result<()> {
+        if let Some(key) = self.sources[i].next().transpose()? {
+            self.heap.push(Reverse((key, i)));
+        }
+
+        Ok(())
+    }
+}
+
+impl<I: Iterator<Item = io::Result<u128>>> Iterator for MergedKeys<I> {
+    type Item = io::Result<u128>;
+
+    fn next(&mut self) -> Option<io::Result<u128>> {
+        while let Some(Reverse((key, i))) = self.heap.pop() {
+            if let Err(error) = self.advance(i) {
+                return Some(Err(error));
+            }
+
+            if self.last == Some(key) {
+                continue;
+            }
+
+            self.last = Some(key);
+
+            return Some(Ok(key));
+        }
+
+        None
+    }
+}
+
+// キーをソートして重複を除き、ファイルに書き込みます（ソート済みのラン）。
+
+pub fn write_run(path: &Path, keys: &mut Vec<u128>) -> io::Result<u64> {
+    keys.par_sort_unstable();
+    keys.dedup();
+
+    let mut writer = KeyWriter::create(path)?;
+
+    for key in keys.drain(..) {
+        writer.write(key)?;
+    }
+
+    writer.finish()
+}
+
+// 昇順に並んだキーのファイルを、メモリにマップして読み込みます。キーの位置（順位）で、局面に番号を付けられます。
+
+pub(crate) struct MappedKeys {
+    mmap: Mmap
+}
+
+impl MappedKeys {
+    // ファイルを開きます。
+
+    pub(crate) fn open(path: &Path) -> io::Result<MappedKeys> {
+        let mmap = map(path)?;
+
+        if mmap.len() % KEY_SIZE != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "truncated key file"));
+        }
+
+        Ok(MappedKeys { mmap })
+    }
+
+    // キーの数を取得します。
+
+    pub(crate) fn len(&self) -> usize {
+        self.mmap.len() / KEY_SIZE
+    }
+
+    // i番目のキーを取得します。
+
+    pub(crate) fn get(&self, i: usize) -> u128 {
+        u128::from_le_bytes(self.keys()[i])
+    }
+
+    // キーの位置を、二分探索で取得します。キーがない場合は、Noneを返します。
+
+    pub(crate) fn rank(&self, key: u128) -> Option<usize> {
+        self.keys().binary_search_by(|bytes| u128::from_le_bytes(*bytes).cmp(&key)).ok()
+    }
+
+    fn keys(&self) -> &[[u8; KEY_SIZE]] {
+        bytemuck::cast_slice(&self.mmap)
+    }
+}
+
+// ファイルを、読み込み専用でメモリにマップします。
+
+pub(crate) fn map(path: &Path) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+
+    // SAFETY: 作業用のディレクトリのファイルなので、マップしている間に他から変更されることはありません。
+
+    unsafe { Mmap::map(&file) }
+}
+
+// ファイルを、書き込み可能でメモリにマップします。lenを指定すると、その長さ（バイト数）のゼロで埋めたファイルを作成します。
+
+pub(crate) fn map_mut(path: &Path, len: Option<u64>) -> io::Result<MmapMut> {
+    let file = OpenOptions::new().read(true).write(true).create(len.is_some()).truncate(len.is_some()).open(path)?;
+
+    if let Some(len) = len {
+        file.set_len(len)?;
+    }
+
+    // SAFETY: 作業用のディレクトリのファイルなので、マップしている間に他から変更されることはありません。
+
+    unsafe { MmapMut::map_mut(&file) }
+}
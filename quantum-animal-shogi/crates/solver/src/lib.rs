@@ -0,0 +1,49 @@
+use quantum_animal_shogi_core::{Game, State, bits};
+
+mod enumeration;
+mod external;
+mod parallel;
+mod progress;
+mod retrograde;
+mod solver;
+mod tablebase;
+
+pub use enumeration::{ExternalEnumerator, Level, count_in_memory};
+pub use external::{KeyReader, KeyWriter, MergedKeys};
+pub use parallel::count_parallel;
+pub use progress::Progress;
+pub use retrograde::{Value, retrograde};
+pub use solver::{ExternalSolver, Solution};
+pub use tablebase::Tablebase;
+
+
+// 以下を参照して作成しました。最初のバージョンは全ての局面をメモリに保持していたので「メモリ不足で失敗」しましたが、ディスクを使用して数えられる（ExternalEnumerator）ようにしました。確認が不十分なので、バグがあったらごめんなさい。。。
+//
+// [「どうぶつしょうぎ」の完全解析](https://www.tanaka.ecc.u-tokyo.ac.jp/ktanaka/dobutsushogi/animal-private.pdf)
+
+
+// 終端局面か判断します。
+
+pub fn is_terminal_state(state: &State) -> bool {
+    // 全ての駒が確定した局面は、終局表（Tablebase）があればその先を探索せずに済みます（ExternalSolver::solve_with_tablebase()）。ただし、数える場合は「どうぶつしょうぎ」の完全解析と比較できるよう、確定した局面の先も探索します。
+
+    // 敵のライオンを取れるなら勝ち確定局面とします。
+
+    for action in Game::legal_actions(state) {
+        let next_state = Game::next_state(state, action);
+
+        if bits(!next_state.ownership).any(|index| next_state.bit_boards[index] == 0 && next_state.pieces[index] == 0b_0000_1000) {
+            return true;
+        }
+    }
+
+    // 勝ち確定局面ではない場合で、敵のライオンの可能性を持つ駒が自陣にいれば負け確定局面とします。
+
+    if bits(!state.ownership).any(|index| state.bit_boards[index] & 0b_000_000_000_111 != 0 && state.pieces[index] & 0b_0000_1000 != 0) {
+        return true;
+    }
+
+    // どちらでもなければ、終端局面ではありません。
+
+    false
+}
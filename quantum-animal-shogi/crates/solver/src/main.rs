@@ -1,137 +1,249 @@
-use std::{collections::{BTreeSet, VecDeque}, iter::once, ops::BitOr};
+use std::{cell::Cell, path::PathBuf, process::ExitCode, time::Instant};
 
-use itertools::Itertools;
+use clap::{ArgGroup, Parser, Subcommand};
+use quantum_animal_shogi_core::{State, Variant};
+use quantum_animal_shogi_solver::{ExternalEnumerator, ExternalSolver, Progress, Tablebase};
 
-use quantum_animal_shogi_core::{Game, State, bits};
-
-
-// 以下を参照して作成しました。ちなみに結果は「メモリ不足で失敗」です。。。速攻で諦めたので、確認が不十分です。バグがあったらごめんなさい。。。
+// ソルバーのコマンドです。
+//
+// * count: 到達可能な局面を、ディスクを使用して数えます。--resumeを指定すると、前回のチェックポイントから再開します。
+// * solve: 到達可能な局面を後退解析して、初期状態の値を出力します。--tablebaseを指定すると、終局表に含まれる局面の先は探索しません（含まれない確定した局面は探索して、その数を出力します）。--resumeを指定すると、前回のチェックポイントから再開します。--outputを指定すると、全ての駒が確定した局面を終局表にして保存します。
+// * query: 終局表（--tablebase）か、solveの作業用のディレクトリ（--work-dir）から、局面の値を取得します。終局表には全ての駒が確定した局面しかありませんが、作業用のディレクトリなら量子的な局面の値も取得できます。
+//
+// 進捗は標準エラー出力に、結果は標準出力に出力します。
+//
+// solveとqueryの値は、標準のルールから手数による引き分け（RuleSet::max_turn）と千日手（RuleSet::repetition_count）を除いたルールでの値です（後退解析は、決着しない局面を引き分けとします）。ゲームのルールと違うので、ヘルプと標準エラー出力にも表示します。
 //
-// [「どうぶつしょうぎ」の完全解析](https://www.tanaka.ecc.u-tokyo.ac.jp/ktanaka/dobutsushogi/animal-private.pdf)
+// --variant classicalで数えると、「どうぶつしょうぎ」の完全解析と同じ246,803,167になります。
 
+#[derive(Parser)]
+#[command(about = "Enumerates and solves the state space of Quantum Animal Shogi")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command
+}
 
-// 状態をu128に変換します。
+#[derive(Subcommand)]
+enum Command {
+    /// Count the positions reachable from the start position
+    Count {
+        #[command(flatten)]
+        start: Start,
+
+        /// Stop after this depth
+        #[arg(long)]
+        max_depth: Option<u32>,
+
+        /// Directory for the key files and the checkpoint
+        #[arg(long, default_value = "solver-work")]
+        work_dir: PathBuf,
+
+        /// Number of keys to sort in memory (16 bytes each)
+        #[arg(long, default_value_t = 1 << 26)]
+        run_capacity: usize,
+
+        /// Resume from the checkpoint in the work directory
+        #[arg(long)]
+        resume: bool
+    },
+
+    /// Solve the positions reachable from the start position (values ignore the turn limit and draws by repetition)
+    Solve {
+        #[command(flatten)]
+        start: Start,
+
+        /// Stop enumerating after this depth (positions at this depth count as draws)
+        #[arg(long)]
+        max_depth: Option<u32>,
+
+        /// Directory for the key, node and edge files
+        #[arg(long, default_value = "solver-work")]
+        work_dir: PathBuf,
+
+        /// Number of keys or edges to sort in memory (16 bytes each)
+        #[arg(long, default_value_t = 1 << 26)]
+        run_capacity: usize,
+
+        /// Resume from the checkpoint in the work directory (pass the same options as before)
+        #[arg(long)]
+        resume: bool,
+
+        /// Tablebase for fully collapsed positions (positions missing from it are searched instead)
+        #[arg(long)]
+        tablebase: Option<PathBuf>,
+
+        /// Write the fully collapsed positions to this tablebase file
+        #[arg(long)]
+        output: Option<PathBuf>
+    },
+
+    /// Look up a position in a tablebase or in a solved work directory (values ignore the turn limit and draws by repetition)
+    #[command(group(ArgGroup::new("source").required(true).args(["tablebase", "work_dir"])))]
+    Query {
+        /// Position in state notation, e.g. "G@c4 L@b4 E@a4 C@b3 / c@b2 e@c1 l@b1 g@a1 0"
+        position: State,
+
+        /// Tablebase file (fully collapsed positions only)
+        #[arg(long)]
+        tablebase: Option<PathBuf>,
+
+        /// Work directory of a finished solve (any position reachable from its start position)
+        #[arg(long)]
+        work_dir: Option<PathBuf>
+    }
+}
 
-fn convert_state_to_u128(state: &State) -> u128 {
-    let pieces: [u128; 8] = (0..8)
-        .into_iter()
-        .map(|i| state.pieces[i] as u128 | if state.pieces[i].count_ones() == 1 || i < 4 { 0 } else { 1 } << 5 | if state.ownership & 1 << i != 0 { 0 } else { 1 } << 6)
-        .collect_array()
-        .unwrap();
+// 値が前提にするルールです。標準のルールとは、max_turnとrepetition_countが違います。
 
-    let result_0 = (0..8)
-        .into_iter()
-        .filter(|i| state.bit_boards[*i] != 0)
-        .map(|i| pieces[i] << 7 * state.bit_boards[i].trailing_zeros())
-        .fold(0, BitOr::bitor);
+const RULES_NOTE: &str = "values assume the standard rules without the turn limit (max_turn) and draws by repetition (repetition_count); positions that never resolve are draws";
 
-    let result_1 = (0..8)
-        .into_iter()
-        .filter(|i| state.bit_boards[*i] == 0)
-        .map(|i| pieces[i])
-        .sorted()
-        .enumerate()
-        .fold(0, |acc, (i, piece)| acc | piece << 7 * i);
+// 初期状態の指定です。
 
-    result_0 | result_1 << 84
-}
+#[derive(clap::Args)]
+struct Start {
+    /// Variant whose initial position is used
+    #[arg(long, value_parser = parse_variant, default_value = "quantum")]
+    variant: Variant,
 
-// 盤面を左右反転します。
+    /// Start position in state notation (overrides --variant)
+    #[arg(long)]
+    position: Option<State>
+}
 
-fn turn_left_right(state: &State) -> State {
-    let mut result = *state;
+impl Start {
+    // 初期状態を取得します。
 
-    result.bit_boards = state.bit_boards
-        .map(|bit_board| {
-            [(0, 2), (3, 5), (6, 8), (9, 11)]
-                .into_iter()
-                .fold(
-                    bit_board,
-                    |acc, (bit_x, bit_y)| {
-                        let bit_x_value = if acc & 1 << bit_x != 0 { 1 } else { 0 };
-                        let bit_y_value = if acc & 1 << bit_y != 0 { 1 } else { 0 };
+    fn state(&self) -> State {
+        self.position.unwrap_or_else(|| self.variant.initial_state())
+    }
+}
 
-                        (acc & !(1 << bit_x) | bit_y_value << bit_x) & !(1 << bit_y) | bit_x_value << bit_y
-                    }
-                )
-        });
+// ゲームの種類を解析します。
 
-    result
+fn parse_variant(s: &str) -> Result<Variant, String> {
+    match s {
+        "quantum"   => Ok(Variant::Quantum),
+        "classical" => Ok(Variant::Classical),
+        _           => Err(format!("unknown variant: {}", s))
+    }
 }
 
-// 終端局面か判断します。
+// 進捗を出力します。深さの探索が終わったら、深さと、初めて到達した局面の数、到達した局面の数、経過時間、その深さで1秒あたりに到達した局面の数を出力します。処理の途中の進捗は、経過時間と一緒に出力します。
+
+struct Reporter {
+    started_at: Instant,
+    level_started_at: Cell<Instant>
+}
 
-fn is_terminal_state(state: &State) -> bool {
-    // // すべての駒が確定しているなら、「どうぶつしょうぎ」の強解決結果に含まれるならそれを活用できるし、そうでなくてもu64で状態を表して別途探索すれば良いので、とりあえずの終端局面とします。
+impl Reporter {
+    fn new() -> Reporter {
+        Reporter { started_at: Instant::now(), level_started_at: Cell::new(Instant::now()) }
+    }
 
-    // if state.pieces.iter().all(|piece| piece.count_ones() == 1) {
-    //     return true;
-    // }
+    fn report(&self, progress: &Progress) {
+        let elapsed = self.started_at.elapsed().as_secs_f64();
 
-    // 敵のライオンを取れるなら勝ち確定局面とします。
+        match progress {
+            Progress::Level(level)                            => {
+                let rate = level.frontier as f64 / self.level_started_at.get().elapsed().as_secs_f64().max(f64::EPSILON);
 
-    for action in Game::legal_actions(&state) {
-        let next_state = Game::next_state(&state, action);
+                eprintln!("depth {:>3}  frontier {:>12}  visited {:>13}  {:>9.1}s  {:>10.0} states/s", level.depth, level.frontier, level.visited, elapsed, rate);
 
-        if bits(!next_state.ownership).any(|index| next_state.bit_boards[index] == 0 && next_state.pieces[index] == 0b_0000_1000) {
-            return true;
+                self.level_started_at.set(Instant::now());
+            }
+            Progress::Expanding { depth, expanded, frontier } => eprintln!("depth {:>3}  expanded {:>12} of {}  {:>9.1}s", depth, expanded, frontier, elapsed),
+            Progress::Merging { depth, merged }               => eprintln!("depth {:>3}  merged {:>14}  {:>9.1}s", depth, merged, elapsed),
+            Progress::Building { built, total }               => eprintln!("built {:>13} of {} nodes  {:>9.1}s", built, total, elapsed),
+            Progress::Linking { linked }                      => eprintln!("linked {:>12} edges  {:>9.1}s", linked, elapsed),
+            Progress::Propagating { distance, decided }       => eprintln!("distance {:>3}  decided {:>12}  {:>9.1}s", distance, decided, elapsed),
+            Progress::Propagated { distance, decided }        => eprintln!("distance {:>3}  decided {:>12}  {:>9.1}s  done", distance, decided, elapsed)
         }
     }
+}
 
-    // 勝ち確定局面ではない場合で、敵のライオンの可能性を持つ駒が自陣にいれば負け確定局面とします。
+// メイン・ルーチンです。
 
-    if bits(!state.ownership).any(|index| state.bit_boards[index] & 0b_000_000_000_111 != 0 && state.pieces[index] & 0b_0000_1000 != 0) {
-        return true;
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(())     => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    // どちらでもなければ、終端局面ではありません。
+fn run(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Count { start, max_depth, work_dir, run_capacity, resume } => {
+            let state = start.state();
+            let enumerator = ExternalEnumerator { work_dir, run_capacity };
+            let reporter = Reporter::new();
 
-    false
-}
+            let count = if resume {
+                enumerator.resume(&state, max_depth, |progress| reporter.report(progress))?
+            } else {
+                enumerator.count(&state, max_depth, |progress| reporter.report(progress))?
+            };
 
-// メイン・ルーチンです。
+            println!("{}", count);
+        }
 
-fn main() {
-    let (mut stack, mut visited) = {
-        // りょうしどうぶつしょうぎの初期状態を設定します。
+        Command::Solve { start, max_depth, work_dir, run_capacity, resume, tablebase, output } => {
+            eprintln!("{}", RULES_NOTE);
 
-        let state = Game::initial_state();
+            let state = start.state();
+            let solver = ExternalSolver { work_dir, run_capacity };
+            let reporter = Reporter::new();
 
-        // 「どうぶつしょうぎ」の初期状態を設定します。この場合で「どうぶつしょうぎ」の完全解析と同じ結果になるなら、処理は概ね正しいはず。 ← 論文の記載と同等の12分で探索が終了し、246,803,167で一致した！
+            let tablebase = tablebase.map(|path| Tablebase::read(&path)).transpose()?;
 
-        // let state = State {
-        //     pieces:     [0b_0_0010, 0b_0_1000, 0b_0_0100, 0b_0_0001, 0b_0_0001, 0b_0_0100, 0b_0_1000, 0b_0_0010],
-        //     ownership:  0b_0000_1111,
-        //     bit_boards: [0b_000_000_000_001, 0b_000_000_000_010, 0b_000_000_000_100, 0b_000_000_010_000, 0b_000_010_000_000, 0b_001_000_000_000, 0b_010_000_000_000, 0b_100_000_000_000],
-        //     turn:       0
-        // };
+            let solution = match (&tablebase, resume) {
+                (Some(tablebase), false) => solver.solve_with_tablebase(&state, max_depth, tablebase, |progress| reporter.report(progress))?,
+                (Some(tablebase), true)  => solver.resume_with_tablebase(&state, max_depth, tablebase, |progress| reporter.report(progress))?,
+                (None, false)            => solver.solve(&state, max_depth, |progress| reporter.report(progress))?,
+                (None, true)             => solver.resume(&state, max_depth, |progress| reporter.report(progress))?
+            };
 
-        let result_0 = once(state).collect::<VecDeque<_>>();
-        let result_1 = result_0.clone().iter().map(convert_state_to_u128).collect::<BTreeSet<_>>();
+            eprintln!("solved {} states in {:.1}s", solution.len(), reporter.started_at.elapsed().as_secs_f64());
 
-        (result_0, result_1)
-    };
+            // 終局表は到達可能な局面から作成するので、含まれない確定した局面があります。それらの局面は探索したので、数を出力しておきます。
 
-    while let Some(state) = stack.pop_back() {
-        if is_terminal_state(&state) {
-            continue;
-        }
+            if let Some(tablebase) = &tablebase {
+                eprintln!("searched {} fully collapsed states missing from the tablebase", tablebase.count_missing(&solution));
+            }
 
-        for action in Game::legal_actions(&state) {
-            let next_state = Game::next_state(&state, action);
+            if let Some(path) = output {
+                let tablebase = Tablebase::from_solution(&solution);
 
-            let next_state_u128 = [convert_state_to_u128(&next_state), convert_state_to_u128(&turn_left_right(&next_state))].into_iter().min().unwrap();
+                tablebase.write(&path)?;
 
-            if visited.contains(&next_state_u128) {
-                continue;
+                eprintln!("wrote {} states to {}", tablebase.len(), path.display());
             }
 
-            visited.insert(next_state_u128);
-            stack.push_back(next_state);
+            println!("{}", solution.value(&state).unwrap());
+        }
+
+        Command::Query { position, tablebase, work_dir } => {
+            eprintln!("{}", RULES_NOTE);
+
+            // 終局表には、全ての駒が確定した局面しか含まれません。作業用のディレクトリの解には、初期状態から到達可能な局面が全て（量子的な局面も）含まれます。
+
+            let value = match (tablebase, work_dir) {
+                (Some(tablebase), _)   => {
+                    if !position.is_classical() {
+                        return Err("the position is not fully collapsed (use --work-dir to query quantum positions)".into());
+                    }
+
+                    Tablebase::read(&tablebase)?.value(&position).ok_or("the position is not in the tablebase")?
+                }
+                (None, Some(work_dir)) => ExternalSolver { work_dir, run_capacity: 0 }.solution()?.value(&position).ok_or("the position is not in the solution")?,
+                (None, None)           => unreachable!("the source group requires --tablebase or --work-dir")
+            };
 
-            println!("{}", visited.len());
+            println!("{}", value);
         }
     }
 
-    println!("{}", visited.len());
+    Ok(())
 }
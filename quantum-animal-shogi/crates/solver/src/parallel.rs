@@ -0,0 +1,55 @@
+use std::sync::Mutex;
+
+use quantum_animal_shogi_core::{Game, State};
+use rayon::prelude::*;
+use rustc_hash::FxHashSet;
+
+use crate::{Level, is_terminal_state};
+
+// 全てのコアを使用して、メモリ上で数えます（count_in_memory()のマルチ・スレッド版です）。
+//
+// 深さごとに、フロンティアの局面を並列に展開します。訪問済みの局面はキーの値でシャード（分割）したハッシュ・セットに保持して、シャードごとにロックします。シャードの数が十分に多いので、ロックの競合はほとんど発生しません。
+//
+// 展開の順序はスレッドのスケジュール次第ですけど、深さごとに初めて到達した局面の集合は順序によらないので、数は毎回同じになります。
+
+const SHARD_BITS: u32 = 10;
+
+pub fn count_parallel(state: &State, max_depth: Option<u32>, mut on_level: impl FnMut(&Level)) -> u64 {
+    let visited = (0..1 << SHARD_BITS).map(|_| Mutex::new(FxHashSet::default())).collect::<Vec<_>>();
+
+    // キーを訪問済みにします。初めて訪問した場合はtrueを返します。
+
+    let insert = |key: u128| visited[shard(key)].lock().unwrap().insert(key);
+
+    insert(state.canonical_key());
+
+    let mut frontier = vec![*state];
+    let mut visited_count = 1;
+
+    on_level(&Level { depth: 0, frontier: 1, visited: visited_count });
+
+    for depth in 1..=max_depth.unwrap_or(u32::MAX) {
+        frontier = frontier
+            .par_iter()
+            .filter(|state| !is_terminal_state(state))
+            .flat_map_iter(|state| Game::legal_actions(state).map(|action| Game::next_state(state, action)))
+            .filter(|next_state| insert(next_state.canonical_key()))
+            .collect();
+
+        if frontier.is_empty() {
+            break;
+        }
+
+        visited_count += frontier.len() as u64;
+
+        on_level(&Level { depth, frontier: frontier.len() as u64, visited: visited_count });
+    }
+
+    visited_count
+}
+
+// キーのシャードを取得します。キーの下位ビットは盤面の右下のマスなので偏りがあるから、混ぜてから上位ビットを使用します。
+
+fn shard(key: u128) -> usize {
+    ((key as u64 ^ (key >> 64) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (64 - SHARD_BITS)) as usize
+}
@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+use crate::Level;
+
+// 処理の途中で、進捗を報告する間隔です。
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+// ディスクを使用して数える、解く場合の進捗です。深さごとの統計（Level）に加えて、時間がかかる処理の途中でも、REPORT_INTERVALごとに報告します。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress {
+    Level(Level),                                            // 深さの探索が終わった
+    Expanding { depth: u32, expanded: u64, frontier: u64 },  // フロンティアを展開中（展開した局面の数 / フロンティアの局面の数）
+    Merging { depth: u32, merged: u64 },                     // ランをマージ中（書き込んだキーの数）
+    Building { built: u64, total: u64 },                     // ノードを作成中（作成したノードの数 / 局面の数）
+    Linking { linked: u64 },                                 // 逆向きの辺をマージ中（書き込んだ辺の数）
+    Propagating { distance: u16, decided: u64 },             // 値を決めている途中（値が決まった局面の数）
+    Propagated { distance: u16, decided: u64 }               // 手数distanceまでの処理が終わった
+}
+
+// 一定の時間ごとに進捗を報告するための、タイマーです。
+
+pub(crate) struct Ticker {
+    reported_at: Instant
+}
+
+impl Ticker {
+    pub(crate) fn new() -> Ticker {
+        Ticker { reported_at: Instant::now() }
+    }
+
+    // 前回からREPORT_INTERVALが経過していたら、trueを返します。時刻の取得にも時間がかかるので、ループの中では、何回かに1回だけ呼び出してください。
+
+    pub(crate) fn tick(&mut self) -> bool {
+        if self.reported_at.elapsed() < REPORT_INTERVAL {
+            return false;
+        }
+
+        self.reported_at = Instant::now();
+
+        true
+    }
+}
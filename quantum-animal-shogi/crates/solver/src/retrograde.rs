@@ -0,0 +1,263 @@
+use std::{fmt, io, iter, mem, sync::atomic::{AtomicU32, Ordering}, vec};
+
+use quantum_animal_shogi_core::{Game, Outcome, RuleSet, State};
+use rayon::prelude::*;
+
+use crate::{Progress, progress::Ticker};
+
+// 後退解析で、局面の勝ち負けと、終局までの手数を求めます（「どうぶつしょうぎ」の完全解析と同じ方法です）。
+//
+// 1. 初期状態から到達可能な局面を全て列挙します。終局した局面と、1手で勝てる（敵のライオンを取れる）局面の先は探索しません。これらの局面が、後退解析の種になります。
+// 2. 局面ごとに、まだ値が決まっていない次の局面の数を数えます。あわせて、次の局面から前の局面への逆向きの辺を作成します。
+// 3. 値が決まった局面から逆向きの辺をたどって、前の局面の値を決めます。次の局面が負けなら前の局面は勝ち、次の局面が全て勝ちなら前の局面は負けです。値が決まった順（終局までの手数の順）に処理するので、勝ちは最短、負けは最長の手数になります。
+// 4. 最後まで値が決まらなかった局面は、どちらも勝てない（千日手になる）ので引き分けです。
+//
+// 手数による引き分け（RuleSet::max_turn）は無視します。
+
+pub(crate) const RULES: RuleSet = RuleSet { max_turn: u16::MAX, repetition_count: 0, ..RuleSet::STANDARD };
+
+// 手番側から見た局面の値です。u16は、終局までの手数です。
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    Win(u16),   // 勝ち
+    Loss(u16),  // 負け
+    Draw        // 引き分け
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Win(distance)  => write!(f, "win in {}", distance),
+            Value::Loss(distance) => write!(f, "loss in {}", distance),
+            Value::Draw           => write!(f, "draw")
+        }
+    }
+}
+
+// 探索せずに値が決まる局面（後退解析の種）の値を取得します。終局した局面と、1手で勝てる局面です。
+
+pub(crate) fn leaf_value(state: &State) -> Option<Value> {
+    match RULES.outcome(state) {
+        Outcome::Win(_)  => return Some(Value::Win(0)),
+        Outcome::Loss(_) => return Some(Value::Loss(0)),
+        _                => ()
+    }
+
+    Game::legal_actions(state).any(|action| matches!(RULES.outcome(&Game::next_state(state, action)), Outcome::Loss(_))).then_some(Value::Win(1))
+}
+
+// 後退解析します。ノードは0〜node_count - 1の番号で表し、seedで探索せずに値が決まるノードの値を、childrenで次のノードの番号を取得します（childrenは、seedがNoneのノードに対してだけ呼び出されます）。次のノードがないノードは、負けとします。種の値は、引き分けや任意の手数でも構いません（終局表の値を種にする場合）。
+//
+// 全てをメモリ上で実行します。局面の数が多い場合は、ディスクを使用するExternalSolverを使用してください（値を決めていく処理は、同じpropagate()です）。
+//
+// childrenは2回（数える時と、逆向きの辺を作成する時）呼び出されるので、同じ結果を返してください。
+
+pub fn retrograde(node_count: usize, seed: impl Fn(usize) -> Option<Value> + Sync, children: impl Fn(usize) -> Vec<u32> + Sync) -> Vec<Value> {
+    let children = |i: usize| {
+        let mut result = children(i);
+
+        result.sort_unstable();
+        result.dedup();
+
+        result
+    };
+
+    // 種の値と、次のノードの数、前のノードの数を数えます。
+
+    let predecessor_counts = (0..node_count).map(|_| AtomicU32::new(0)).collect::<Vec<_>>();
+
+    let mut nodes = (0..node_count)
+        .into_par_iter()
+        .map(|i| {
+            if let Some(value) = seed(i) {
+                return encode_node(value);
+            }
+
+            let children = children(i);
+
+            if children.is_empty() {
+                return encode_node(Value::Loss(0));
+            }
+
+            for child in &children {
+                predecessor_counts[*child as usize].fetch_add(1, Ordering::Relaxed);
+            }
+
+            children.len() as u32
+        })
+        .collect::<Vec<_>>();
+
+    // 逆向きの辺を作成します。ノードiの前のノードは、predecessors[offsets[i]..offsets[i + 1]]です。
+
+    let offsets = [0].into_iter()
+        .chain(predecessor_counts.iter().scan(0_u64, |acc, count| {
+            *acc += count.load(Ordering::Relaxed) as u64;
+
+            Some(*acc)
+        }))
+        .collect::<Vec<_>>();
+
+    let predecessors = (0..offsets[node_count]).map(|_| AtomicU32::new(0)).collect::<Vec<_>>();
+    let filled = (0..node_count).map(|_| AtomicU32::new(0)).collect::<Vec<_>>();
+
+    (0..node_count).into_par_iter().filter(|i| decode_node(nodes[*i]).is_none()).for_each(|i| {
+        for child in children(i) {
+            let position = offsets[child as usize] + filled[child as usize].fetch_add(1, Ordering::Relaxed) as u64;
+
+            predecessors[position as usize].store(i as u32, Ordering::Relaxed);
+        }
+    });
+
+    let predecessors = predecessors.into_iter().map(AtomicU32::into_inner).collect::<Vec<_>>();
+
+    // 値が決まったノードから、前のノードの値を決めていきます。
+
+    let mut buckets = Vec::<Vec<u32>>::new();
+
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(value) = decode_node(*node) {
+            Buckets::push(&mut buckets, value, i as u32).unwrap();
+        }
+    }
+
+    propagate(&mut nodes, &offsets, &predecessors, &mut buckets, 0, |_| ()).unwrap();
+
+    // 値が決まらなかったノードは、引き分けです。
+
+    nodes.into_iter().map(|node| decode_node(node).unwrap_or(Value::Draw)).collect()
+}
+
+// ノードの状態を、u32で表します。上位2ビットが0なら値が決まっていないノードで、下位ビットはまだ値が決まっていない次のノードの数です。上位2ビットが1なら勝ち、2なら負け、3なら引き分けで、下位16ビットが終局までの手数です。
+
+const WIN_NODE: u32 = 1 << 30;
+const LOSS_NODE: u32 = 2 << 30;
+const DRAW_NODE: u32 = 3 << 30;
+const KIND_MASK: u32 = 3 << 30;
+
+pub(crate) fn encode_node(value: Value) -> u32 {
+    match value {
+        Value::Win(distance)  => WIN_NODE | distance as u32,
+        Value::Loss(distance) => LOSS_NODE | distance as u32,
+        Value::Draw           => DRAW_NODE
+    }
+}
+
+// ノードの値を取得します。値が決まっていないノードの場合は、Noneを返します。
+
+pub(crate) fn decode_node(node: u32) -> Option<Value> {
+    match node & KIND_MASK {
+        WIN_NODE  => Some(Value::Win(node as u16)),
+        LOSS_NODE => Some(Value::Loss(node as u16)),
+        DRAW_NODE => Some(Value::Draw),
+        _         => None
+    }
+}
+
+// 終局までの手数ごとの、値が決まったノードの集合（バケット）です。引き分けのノードは、前のノードの値を決めないので入れません。
+
+pub(crate) trait Buckets {
+    type Nodes: Iterator<Item = io::Result<u32>>;
+
+    // バケットの数（最大の手数 + 1）を取得します。
+
+    fn len(&self) -> usize;
+
+    // ノードを、valueの手数のバケットに入れます。
+
+    fn push(&mut self, value: Value, node: u32) -> io::Result<()>;
+
+    // 手数がdistanceのバケットを取り出します。
+
+    fn take(&mut self, distance: u16) -> io::Result<Self::Nodes>;
+
+    // 手数がdistanceのバケットの処理が終わった後に、呼び出されます。nodesは、その時点のノードの状態です。
+
+    fn finish(&mut self, _distance: u16, _nodes: &[u32]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buckets for Vec<Vec<u32>> {
+    type Nodes = iter::Map<vec::IntoIter<u32>, fn(u32) -> io::Result<u32>>;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn push(&mut self, value: Value, node: u32) -> io::Result<()> {
+        if let Value::Win(distance) | Value::Loss(distance) = value {
+            if Vec::len(self) <= distance as usize {
+                self.resize_with(distance as usize + 1, Vec::new);
+            }
+
+            self[distance as usize].push(node);
+        }
+
+        Ok(())
+    }
+
+    fn take(&mut self, distance: u16) -> io::Result<Self::Nodes> {
+        Ok(mem::take(&mut self[distance as usize]).into_iter().map(Ok))
+    }
+}
+
+// 値を決めている途中で、進捗を報告するか確認する間隔（ノードの数）です。
+
+const TICK_NODES: usize = 1 << 16;
+
+// 値が決まったノードから逆向きの辺をたどって、終局までの手数の順に前のノードの値を決めていきます。nodesはencode_node()の形式で、ノードiの前のノードはpredecessors[offsets[i]..offsets[i + 1]]です。distanceより前の手数のバケットは、処理済みとします（チェックポイントから再開する場合）。on_progressには、手数ごとの処理が終わった時と、処理の途中で一定の時間ごとに、進捗を報告します。
+
+pub(crate) fn propagate(nodes: &mut [u32], offsets: &[u64], predecessors: &[u32], buckets: &mut impl Buckets, mut distance: u16, mut on_progress: impl FnMut(&Progress)) -> io::Result<()> {
+    let mut decided = 0_u64;
+    let mut ticker = Ticker::new();
+
+    while (distance as usize) < buckets.len() {
+        let next_distance = distance.checked_add(1).ok_or_else(|| io::Error::other("distance to the end of the game is too long"))?;
+
+        for (i, child) in buckets.take(distance)?.enumerate() {
+            let child = child? as usize;
+
+            if i.is_multiple_of(TICK_NODES) && ticker.tick() {
+                on_progress(&Progress::Propagating { distance, decided });
+            }
+
+            for predecessor in &predecessors[offsets[child] as usize..offsets[child + 1] as usize] {
+                let predecessor = *predecessor as usize;
+
+                if decode_node(nodes[predecessor]).is_some() {
+                    continue;
+                }
+
+                // 次のノードが負けなら勝ち、次のノードが全て勝ちなら負けです。手数の順に処理するので、勝ちは最短、負けは最長の手数になります。
+
+                let value = match decode_node(nodes[child]) {
+                    Some(Value::Loss(_)) => Value::Win(next_distance),
+                    Some(Value::Win(_))  => {
+                        nodes[predecessor] -= 1;
+
+                        if nodes[predecessor] != 0 {
+                            continue;
+                        }
+
+                        Value::Loss(next_distance)
+                    }
+                    _                    => unreachable!()
+                };
+
+                nodes[predecessor] = encode_node(value);
+                buckets.push(value, predecessor as u32)?;
+
+                decided += 1;
+            }
+        }
+
+        buckets.finish(distance, nodes)?;
+
+        on_progress(&Progress::Propagated { distance, decided });
+
+        distance = next_distance;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,620 @@
+use std::{fs::{self, File, OpenOptions, create_dir_all, remove_dir_all, remove_file}, io::{self, BufReader, BufWriter, ErrorKind, Read, Write}, iter, path::{Path, PathBuf}, time::{Duration, Instant}};
+
+use memmap2::Mmap;
+use quantum_animal_shogi_core::{Game, State};
+use rayon::prelude::*;
+
+use crate::{ExternalEnumerator, Progress, Tablebase, Value, external::{KeyReader, MappedKeys, MergedKeys, map, map_mut, write_run}, progress::Ticker, retrograde::{Buckets, decode_node, encode_node, leaf_value, propagate}};
+
+// ディスクを使用して、後退解析で局面を解きます（外部メモリの後退解析）。以下を実行します。
+//
+// 1. ExternalEnumeratorで、到達可能な局面を列挙します。探索せずに値が決まる局面（後退解析の種）の先は探索しません。列挙したキーのファイル（keys）は昇順なので、キーの位置（順位）を局面の番号にします。
+// 2. 局面ごとに次の局面の番号を二分探索で求めて、ノードのファイル（nodes）に種の値か、次の局面の数を書き込みます。あわせて、逆向きの辺（次の局面の番号, 局面の番号）をランのファイルに書き込み、マージして前の局面の番号のファイル（predecessors）と、その開始位置のファイル（offsets）を作成します。
+// 3. 値が決まった局面を手数ごとのバケットのファイルに入れて、retrograde()と同じpropagate()で値を決めていきます。
+//
+// ノードと逆向きの辺のファイルはメモリにマップするので、メモリに収まらなくても（遅くはなりますけど）解けます。局面の番号はu32なので、局面の数がu32::MAXを超える場合はエラーになります。
+//
+// メモリの使用量はrun_capacity * 16バイト程度で、ディスクは局面の数 * 28バイトと、逆向きの辺の数 * 4バイト（ランのファイルは一時的に * 16バイト）程度を使用します。終了後、work_dirにはキーとノードのファイルが残り、Solutionはこれらをメモリにマップして値を取得します。
+//
+// 途中で止まっても、resume()でチェックポイントから再開できます。列挙の途中ならExternalEnumeratorのチェックポイントから、ノードと逆向きの辺の作成の途中なら作成の最初から、値を決めている途中なら最後にチェックポイントを書き込んだ手数から再開します。ノードのファイルは処理中に書き換えるので、チェックポイントにはノードのファイルのコピー（スナップショット）と、バケットのファイルの長さを保存します。コピーには時間がかかるので、値を決めている途中のチェックポイントは、CHECKPOINT_INTERVALごとに書き込みます。
+
+// 一度に処理する局面の数です。
+
+const CHUNK_SIZE: usize = 1 << 16;
+const BUFFER_SIZE: usize = 1 << 20;
+
+// マージの途中で、進捗を報告するか確認する間隔（辺の数）です。
+
+const TICK_EDGES: u64 = 1 << 16;
+
+// 値を決めている途中で、チェックポイントを書き込む間隔です。
+
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub struct ExternalSolver {
+    pub work_dir: PathBuf,   // 作業用のディレクトリ
+    pub run_capacity: usize  // メモリに貯める逆向きの辺の数
+}
+
+impl ExternalSolver {
+    // 初期状態から到達可能な全ての局面を解きます。max_depthを指定すると、その深さで列挙を打ち切ります（打ち切った深さの局面は、次の局面の値が分からないので引き分けになります）。on_progressには、列挙と後退解析の進捗を報告します。
+
+    pub fn solve(&self, state: &State, max_depth: Option<u32>, on_progress: impl FnMut(&Progress)) -> io::Result<Solution> {
+        self.solve_with(state, max_depth, leaf_value, false, on_progress)
+    }
+
+    // 終局表を使用して解きます。終局表に含まれる（全ての駒が確定した）局面の先は探索せず、終局表の値を使用します。
+
+    pub fn solve_with_tablebase(&self, state: &State, max_depth: Option<u32>, tablebase: &Tablebase, on_progress: impl FnMut(&Progress)) -> io::Result<Solution> {
+        self.solve_with(state, max_depth, |state| leaf_value(state).or_else(|| tablebase.value(state)), false, on_progress)
+    }
+
+    // チェックポイントから、解くのを再開します。止まる前と同じ引数で呼び出してください。チェックポイントが別の初期状態のものの場合は、エラーになります。
+
+    pub fn resume(&self, state: &State, max_depth: Option<u32>, on_progress: impl FnMut(&Progress)) -> io::Result<Solution> {
+        self.solve_with(state, max_depth, leaf_value, true, on_progress)
+    }
+
+    // 終局表を使用して、解くのを再開します。
+
+    pub fn resume_with_tablebase(&self, state: &State, max_depth: Option<u32>, tablebase: &Tablebase, on_progress: impl FnMut(&Progress)) -> io::Result<Solution> {
+        self.solve_with(state, max_depth, |state| leaf_value(state).or_else(|| tablebase.value(state)), true, on_progress)
+    }
+
+    // 解き終わった作業用のディレクトリから、解を開きます。量子的な局面も含めて、解いた局面の値を取得できます。解き終わっていない場合は、エラーになります。
+
+    pub fn solution(&self) -> io::Result<Solution> {
+        let is_solved = match Checkpoint::read(&self.checkpoint_path()) {
+            Ok(checkpoint)                                    => matches!(checkpoint.stage, Stage::Solved),
+            Err(error) if error.kind() == ErrorKind::NotFound => false,
+            Err(error)                                        => return Err(error)
+        };
+
+        if !is_solved {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "the work directory is not solved yet"));
+        }
+
+        Solution::open(&self.keys_path(), &self.nodes_path())
+    }
+
+    // 初期状態から到達可能な全ての局面を解きます。leaf_valueがSomeを返す局面は、その先を探索せずに、その値を使用します。resumeがtrueなら、チェックポイントから再開します。
+
+    fn solve_with(&self, state: &State, max_depth: Option<u32>, leaf_value: impl Fn(&State) -> Option<Value> + Sync, resume: bool, mut on_progress: impl FnMut(&Progress)) -> io::Result<Solution> {
+        create_dir_all(&self.work_dir)?;
+
+        let root = state.canonical_key();
+
+        // 再開する場合は、チェックポイントの段階から続けます。チェックポイントがなければ、列挙の途中です。再開しない場合は、前回のチェックポイントを削除しておきます。
+
+        let stage = if resume {
+            self.read_checkpoint(root)?
+        } else {
+            if self.checkpoint_path().exists() {
+                remove_file(self.checkpoint_path())?;
+            }
+
+            None
+        };
+
+        // 到達可能な局面を列挙します。
+
+        let stage = match stage {
+            Some(stage) => stage,
+            None        => {
+                let enumerator = ExternalEnumerator { work_dir: self.work_dir.join("enumeration"), run_capacity: self.run_capacity };
+                let is_leaf = |state: &State| leaf_value(state).is_some();
+
+                let level = if resume {
+                    enumerator.resume_enumeration(state, max_depth, is_leaf, &mut on_progress)?
+                } else {
+                    enumerator.enumerate(state, max_depth, is_leaf, &mut on_progress)?
+                };
+
+                // 列挙したキーのファイルを移動してから、チェックポイントを書き込みます。移動した後で止まった場合は、移動済みです。
+
+                if enumerator.visited_path(level.depth).exists() {
+                    fs::rename(enumerator.visited_path(level.depth), self.keys_path())?;
+                }
+
+                Checkpoint { root, stage: Stage::Enumerated }.write(&self.checkpoint_path())?;
+
+                remove_dir_all(&enumerator.work_dir)?;
+
+                Stage::Enumerated
+            }
+        };
+
+        // ノードと逆向きの辺のファイルを作成して、値を決めていきます。値を決めている途中から再開する場合は、ノードのファイルをスナップショットに戻します。
+
+        let resumed = match stage {
+            Stage::Enumerated                        => {
+                let mut buckets = BucketFiles::create(self, root)?;
+
+                self.build_graph(&leaf_value, &mut buckets, &mut on_progress)?;
+
+                Some((buckets, 0))
+            }
+            Stage::Propagating { distance, lengths } => {
+                fs::copy(self.snapshot_path(distance), self.nodes_path())?;
+
+                Some((BucketFiles::open(self, root, &lengths)?, distance))
+            }
+            Stage::Solved                            => None
+        };
+
+        if let Some((mut buckets, distance)) = resumed {
+            self.propagate(&mut buckets, distance, &mut on_progress)?;
+
+            Checkpoint { root, stage: Stage::Solved }.write(&self.checkpoint_path())?;
+        }
+
+        // 作業用のファイルを削除します。
+
+        for path in [self.offsets_path(), self.predecessors_path()] {
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
+
+        if self.buckets_dir().exists() {
+            remove_dir_all(self.buckets_dir())?;
+        }
+
+        self.remove_snapshots(None)?;
+
+        Solution::open(&self.keys_path(), &self.nodes_path())
+    }
+
+    // チェックポイントを読み込んで、段階を取得します。チェックポイントがない場合は、Noneを返します。
+
+    fn read_checkpoint(&self, root: u128) -> io::Result<Option<Stage>> {
+        let checkpoint = match Checkpoint::read(&self.checkpoint_path()) {
+            Ok(checkpoint)                                    => checkpoint,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error)                                        => return Err(error)
+        };
+
+        if checkpoint.root != root {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "checkpoint was made for another initial state"));
+        }
+
+        Ok(Some(checkpoint.stage))
+    }
+
+    // ノードのファイルと、逆向きの辺のファイルを作成します。値が決まったノードは、バケットに入れます。最後に、手数0から値を決めていくためのチェックポイントを書き込みます。
+
+    fn build_graph(&self, leaf_value: &(impl Fn(&State) -> Option<Value> + Sync), buckets: &mut BucketFiles, on_progress: &mut impl FnMut(&Progress)) -> io::Result<()> {
+        let keys = MappedKeys::open(&self.keys_path())?;
+        let mut ticker = Ticker::new();
+
+        if u32::try_from(keys.len()).is_err() {
+            return Err(io::Error::other(format!("too many states to solve: {}", keys.len())));
+        }
+
+        let mut mmap = map_mut(&self.nodes_path(), Some(keys.len() as u64 * 4))?;
+        let nodes = bytemuck::cast_slice_mut::<u8, u32>(&mut mmap);
+
+        // ノードの状態を書き込んで、逆向きの辺をランのファイルに書き込みます。逆向きの辺は、(次の局面の番号 << 64) | 局面の番号で表します。
+
+        let mut run_paths = Vec::new();
+        let mut edges = Vec::with_capacity(self.run_capacity);
+
+        for start in (0..keys.len()).step_by(CHUNK_SIZE) {
+            let end = (start + CHUNK_SIZE).min(keys.len());
+
+            let chunk = (start..end)
+                .into_par_iter()
+                .map(|i| {
+                    let state = State::from_compact_key(keys.get(i), 0).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+                    if let Some(value) = leaf_value(&state) {
+                        return Ok((encode_node(value), Vec::new()));
+                    }
+
+                    // max_depthで列挙を打ち切った局面は、次の局面が列挙されていません。値が分からないので、引き分けとします。
+
+                    let Some(mut children) = Game::legal_actions(&state).map(|action| keys.rank(Game::next_state(&state, action).canonical_key()).map(|child| child as u32)).collect::<Option<Vec<_>>>() else {
+                        return Ok((encode_node(Value::Draw), Vec::new()));
+                    };
+
+                    children.sort_unstable();
+                    children.dedup();
+
+                    if children.is_empty() {
+                        return Ok((encode_node(Value::Loss(0)), Vec::new()));
+                    }
+
+                    Ok((children.len() as u32, children))
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+
+            for (i, (node, children)) in (start..end).zip(chunk) {
+                nodes[i] = node;
+
+                if let Some(value) = decode_node(node) {
+                    buckets.push(value, i as u32)?;
+                }
+
+                for child in children {
+                    edges.push(((child as u128) << 64) | i as u128);
+
+                    if edges.len() >= self.run_capacity {
+                        run_paths.push(self.run_path(run_paths.len()));
+                        write_run(run_paths.last().unwrap(), &mut edges)?;
+                    }
+                }
+            }
+
+            if ticker.tick() {
+                on_progress(&Progress::Building { built: end as u64, total: keys.len() as u64 });
+            }
+        }
+
+        if !edges.is_empty() {
+            run_paths.push(self.run_path(run_paths.len()));
+            write_run(run_paths.last().unwrap(), &mut edges)?;
+        }
+
+        mmap.flush()?;
+
+        // ランをマージして、次の局面の番号の順に前の局面の番号を書き込みます。ノードiの前のノードは、predecessors[offsets[i]..offsets[i + 1]]です。
+
+        let mut offsets = BufWriter::with_capacity(BUFFER_SIZE, File::create(self.offsets_path())?);
+        let mut predecessors = BufWriter::with_capacity(BUFFER_SIZE, File::create(self.predecessors_path())?);
+
+        let mut offset = 0_u64;
+        let mut node = 0;
+
+        for edge in MergedKeys::new(run_paths.iter().map(|path| KeyReader::open(path)).collect::<io::Result<Vec<_>>>()?)? {
+            let edge = edge?;
+
+            while node <= (edge >> 64) as usize {
+                offsets.write_all(&offset.to_ne_bytes())?;
+                node += 1;
+            }
+
+            predecessors.write_all(&(edge as u32).to_ne_bytes())?;
+            offset += 1;
+
+            if offset.is_multiple_of(TICK_EDGES) && ticker.tick() {
+                on_progress(&Progress::Linking { linked: offset });
+            }
+        }
+
+        while node <= keys.len() {
+            offsets.write_all(&offset.to_ne_bytes())?;
+            node += 1;
+        }
+
+        offsets.flush()?;
+        predecessors.flush()?;
+
+        for path in &run_paths {
+            remove_file(path)?;
+        }
+
+        buckets.checkpoint(0, bytemuck::cast_slice(&mmap))
+    }
+
+    // 値が決まったノードから、手数distanceから順に、前のノードの値を決めていきます。
+
+    fn propagate(&self, buckets: &mut BucketFiles, distance: u16, on_progress: &mut impl FnMut(&Progress)) -> io::Result<()> {
+        let mut nodes = map_mut(&self.nodes_path(), None)?;
+        let offsets = map(&self.offsets_path())?;
+        let predecessors = map(&self.predecessors_path())?;
+
+        propagate(bytemuck::cast_slice_mut(&mut nodes), bytemuck::cast_slice(&offsets), bytemuck::cast_slice(&predecessors), buckets, distance, on_progress)?;
+
+        nodes.flush()
+    }
+
+    // スナップショットのファイルを削除します。exceptを指定すると、その手数のスナップショットは残します。
+
+    fn remove_snapshots(&self, except: Option<u16>) -> io::Result<()> {
+        for entry in fs::read_dir(&self.work_dir)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|extension| extension == "snapshot") && except.is_none_or(|distance| path != self.snapshot_path(distance)) {
+                remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // チェックポイントのファイルのパスを取得します。
+
+    fn checkpoint_path(&self) -> PathBuf {
+        self.work_dir.join("checkpoint")
+    }
+
+    // キーのファイルのパスを取得します。
+
+    fn keys_path(&self) -> PathBuf {
+        self.work_dir.join("solution.keys")
+    }
+
+    // ノードのファイルのパスを取得します。
+
+    fn nodes_path(&self) -> PathBuf {
+        self.work_dir.join("solution.nodes")
+    }
+
+    // 前のノードの開始位置のファイルのパスを取得します。
+
+    fn offsets_path(&self) -> PathBuf {
+        self.work_dir.join("offsets")
+    }
+
+    // 前のノードのファイルのパスを取得します。
+
+    fn predecessors_path(&self) -> PathBuf {
+        self.work_dir.join("predecessors")
+    }
+
+    // ノードのスナップショットのファイルのパスを取得します。
+
+    fn snapshot_path(&self, distance: u16) -> PathBuf {
+        self.work_dir.join(format!("nodes-{}.snapshot", distance))
+    }
+
+    // バケットのディレクトリのパスを取得します。
+
+    fn buckets_dir(&self) -> PathBuf {
+        self.work_dir.join("buckets")
+    }
+
+    // バケットのファイルのパスを取得します。
+
+    fn bucket_path(&self, distance: u16) -> PathBuf {
+        self.buckets_dir().join(format!("bucket-{}.nodes", distance))
+    }
+
+    // ランのファイルのパスを取得します。
+
+    fn run_path(&self, i: usize) -> PathBuf {
+        self.work_dir.join(format!("run-{}.edges", i))
+    }
+}
+
+// 手数ごとのバケットのファイルです。ファイルの形式は、ノードの番号（u32）を並べただけです。処理が終わったバケットのファイルは、チェックポイントを書き込んだ後で削除します。
+
+struct BucketFiles<'a> {
+    solver: &'a ExternalSolver,
+    root: u128,
+    writers: Vec<Option<BufWriter<File>>>,
+    checkpointed_at: Instant
+}
+
+impl<'a> BucketFiles<'a> {
+    // 空のバケットを作成します。前回のファイルが残っている場合は、削除します。
+
+    fn create(solver: &'a ExternalSolver, root: u128) -> io::Result<BucketFiles<'a>> {
+        if solver.buckets_dir().exists() {
+            remove_dir_all(solver.buckets_dir())?;
+        }
+
+        create_dir_all(solver.buckets_dir())?;
+
+        Ok(BucketFiles { solver, root, writers: Vec::new(), checkpointed_at: Instant::now() })
+    }
+
+    // チェックポイントのバケットを開きます。チェックポイントの後で書き込んだノードは、ファイルを切り詰めて取り除きます。
+
+    fn open(solver: &'a ExternalSolver, root: u128, lengths: &[u64]) -> io::Result<BucketFiles<'a>> {
+        for entry in fs::read_dir(solver.buckets_dir())? {
+            let path = entry?.path();
+            let distance = path.file_stem().and_then(|stem| stem.to_str()?.strip_prefix("bucket-")?.parse::<usize>().ok());
+
+            match distance.and_then(|distance| lengths.get(distance)) {
+                Some(length) if *length > 0 => OpenOptions::new().write(true).open(path)?.set_len(*length)?,
+                _                           => remove_file(path)?
+            }
+        }
+
+        Ok(BucketFiles { solver, root, writers: lengths.iter().map(|_| None).collect(), checkpointed_at: Instant::now() })
+    }
+
+    // 手数distanceから再開できるよう、チェックポイントを書き込みます。nodesは、その時点のノードの状態です。
+
+    fn checkpoint(&mut self, distance: u16, nodes: &[u32]) -> io::Result<()> {
+        // バケットのファイルに書き込んで、長さを記録します。
+
+        let mut lengths = Vec::with_capacity(self.writers.len());
+
+        for (i, writer) in self.writers.iter_mut().enumerate() {
+            if let Some(writer) = writer {
+                writer.flush()?;
+            }
+
+            let path = self.solver.bucket_path(i as u16);
+
+            lengths.push(if path.exists() { fs::metadata(path)?.len() } else { 0 });
+        }
+
+        // スナップショットを書き込んでから、チェックポイントを書き込みます。前のスナップショットと、処理が終わったバケットのファイルは、その後で削除します。
+
+        fs::write(self.solver.snapshot_path(distance), bytemuck::cast_slice::<u32, u8>(nodes))?;
+
+        Checkpoint { root: self.root, stage: Stage::Propagating { distance, lengths } }.write(&self.solver.checkpoint_path())?;
+
+        self.solver.remove_snapshots(Some(distance))?;
+
+        for finished_distance in 0..distance {
+            if self.solver.bucket_path(finished_distance).exists() {
+                remove_file(self.solver.bucket_path(finished_distance))?;
+            }
+        }
+
+        self.checkpointed_at = Instant::now();
+
+        Ok(())
+    }
+}
+
+impl Buckets for BucketFiles<'_> {
+    type Nodes = NodeReader;
+
+    fn len(&self) -> usize {
+        self.writers.len()
+    }
+
+    fn push(&mut self, value: Value, node: u32) -> io::Result<()> {
+        let (Value::Win(distance) | Value::Loss(distance)) = value else {
+            return Ok(());
+        };
+
+        if self.writers.len() <= distance as usize {
+            self.writers.resize_with(distance as usize + 1, || None);
+        }
+
+        // ファイルは、初めてノードを入れる時に開きます。手数ごとのファイルを同時に開くので、バッファーは小さくしておきます。
+
+        if self.writers[distance as usize].is_none() {
+            self.writers[distance as usize] = Some(BufWriter::new(OpenOptions::new().create(true).append(true).open(self.solver.bucket_path(distance))?));
+        }
+
+        self.writers[distance as usize].as_mut().unwrap().write_all(&node.to_ne_bytes())
+    }
+
+    fn take(&mut self, distance: u16) -> io::Result<NodeReader> {
+        if let Some(mut writer) = self.writers[distance as usize].take() {
+            writer.flush()?;
+        }
+
+        let path = self.solver.bucket_path(distance);
+
+        Ok(NodeReader { reader: if path.exists() { Some(BufReader::with_capacity(BUFFER_SIZE, File::open(path)?)) } else { None } })
+    }
+
+    fn finish(&mut self, distance: u16, nodes: &[u32]) -> io::Result<()> {
+        if self.checkpointed_at.elapsed() < CHECKPOINT_INTERVAL {
+            return Ok(());
+        }
+
+        self.checkpoint(distance + 1, nodes)
+    }
+}
+
+// チェックポイントの段階です。
+
+enum Stage {
+    Enumerated,                                        // 局面の列挙が終わった
+    Propagating { distance: u16, lengths: Vec<u64> },  // 手数distance - 1までの値が決まった（lengthsは、バケットのファイルの長さ）
+    Solved                                             // 解き終わった
+}
+
+// チェックポイントです。ファイルには、初期状態のキー（16進数）と段階を、空白で区切って1行で書きます。値を決めている途中の段階は、propagatingと手数、バケットのファイルの長さです。
+
+struct Checkpoint {
+    root: u128,
+    stage: Stage
+}
+
+impl Checkpoint {
+    // ファイルに書き込みます。途中で止まっても壊れないよう、一時ファイルに書き込んでから名前を変更します。
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let stage = match &self.stage {
+            Stage::Enumerated                        => "enumerated".to_string(),
+            Stage::Propagating { distance, lengths } => iter::once(format!("propagating {}", distance)).chain(lengths.iter().map(u64::to_string)).collect::<Vec<_>>().join(" "),
+            Stage::Solved                            => "solved".to_string()
+        };
+
+        let temporary_path = path.with_extension("tmp");
+
+        fs::write(&temporary_path, format!("{:032x} {}\n", self.root, stage))?;
+        fs::rename(&temporary_path, path)
+    }
+
+    // ファイルから読み込みます。
+
+    fn read(path: &Path) -> io::Result<Checkpoint> {
+        let invalid = || io::Error::new(ErrorKind::InvalidData, "invalid checkpoint");
+
+        let text = fs::read_to_string(path)?;
+        let fields = text.split_whitespace().collect::<Vec<_>>();
+
+        let stage = match fields.get(1..) {
+            Some(["enumerated"])                          => Stage::Enumerated,
+            Some(["propagating", distance, lengths @ ..]) => Stage::Propagating {
+                distance: distance.parse().map_err(|_| invalid())?,
+                lengths: lengths.iter().map(|length| length.parse().map_err(|_| invalid())).collect::<io::Result<_>>()?
+            },
+            Some(["solved"])                              => Stage::Solved,
+            _                                             => return Err(invalid())
+        };
+
+        Ok(Checkpoint { root: u128::from_str_radix(fields[0], 16).map_err(|_| invalid())?, stage })
+    }
+}
+
+// バケットのファイルから、ノードの番号を読み込みます。
+
+struct NodeReader {
+    reader: Option<BufReader<File>>
+}
+
+impl Iterator for NodeReader {
+    type Item = io::Result<u32>;
+
+    fn next(&mut self) -> Option<io::Result<u32>> {
+        let mut bytes = [0; 4];
+
+        match self.reader.as_mut()?.read_exact(&mut bytes) {
+            Ok(())                                                 => Some(Ok(u32::from_ne_bytes(bytes))),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => None,
+            Err(error)                                             => Some(Err(error))
+        }
+    }
+}
+
+// 後退解析の結果です。ExternalSolverのキーとノードのファイルをメモリにマップして、局面の値を取得します。
+
+pub struct Solution {
+    keys: MappedKeys,
+    nodes: Mmap
+}
+
+impl Solution {
+    // ファイルを開きます。
+
+    fn open(keys_path: &Path, nodes_path: &Path) -> io::Result<Solution> {
+        let keys = MappedKeys::open(keys_path)?;
+        let nodes = map(nodes_path)?;
+
+        if nodes.len() != keys.len() * 4 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "solution files do not match"));
+        }
+
+        Ok(Solution { keys, nodes })
+    }
+
+    // 局面の数を取得します。
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 局面の値を取得します。解いた局面に含まれない場合は、Noneを返します。
+
+    pub fn value(&self, state: &State) -> Option<Value> {
+        self.keys.rank(state.canonical_key()).map(|i| self.value_at(i))
+    }
+
+    // 局面のキーと値のイテレーターを、キーの昇順で取得します。
+
+    pub fn iter(&self) -> impl Iterator<Item = (u128, Value)> + '_ {
+        (0..self.len()).map(|i| (self.keys.get(i), self.value_at(i)))
+    }
+
+    // i番目の局面の値を取得します。値が決まらなかった局面は、引き分けです。
+
+    fn value_at(&self, i: usize) -> Value {
+        decode_node(bytemuck::cast_slice::<u8, u32>(&self.nodes)[i]).unwrap_or(Value::Draw)
+    }
+}
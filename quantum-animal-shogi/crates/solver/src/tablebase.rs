@@ -0,0 +1,197 @@
+use std::{fs::File, io::{self, BufReader, BufWriter, ErrorKind, Read, Write}, path::Path};
+
+use quantum_animal_shogi_core::{Outcome, State};
+
+use crate::{ExternalSolver, Progress, Solution, Value, retrograde::RULES};
+
+// 全ての駒が確定した（State::is_classical()）局面の終局表です。全ての駒が確定すると「どうぶつしょうぎ」と同じなので、解いておけば、エンジンやソルバーはその先を探索せずに済みます。
+//
+// 終局した局面はRuleSet::outcome()で値が分かるので、終局表には終局していない局面だけを保持します。終局していない局面の持ち駒にライオンはないので、局面を64ビットのキーで表せます。
+//
+// * 0〜47ビット: 盤面。マス（ビット・ボードのビットの位置）ごとに4ビットで、マスnの駒は4 * nビット目からになります。
+//   * 0〜2ビット: 駒（0は駒なし、1〜5はひよこ、きりん、ぞう、ライオン、にわとり）
+//   * 3ビット: 敵の駒の場合に1
+// * 48〜59ビット: 持ち駒の数。自分のひよこ、きりん、ぞう、敵のひよこ、きりん、ぞうの順に2ビットずつです。
+//
+// 左右反転した局面は同じ局面とみなして、キーの小さい方を使用します。
+//
+// ファイルの形式は、以下をリトル・エンディアンで並べたものです。1局面あたり10バイトです。
+//
+// * マジック・ナンバー（8バイト）
+// * 局面の数（u64）
+// * キーの昇順に、局面のキー（u64）
+// * キーの昇順に、局面の値（u16）。上位2ビットが0なら引き分け、1なら勝ち、2なら負けで、下位14ビットが終局までの手数です。
+
+const MAGIC: &[u8; 8] = b"QASTB001";
+const BUFFER_SIZE: usize = 1 << 20;
+
+const WIN: u16 = 1 << 14;
+const LOSS: u16 = 2 << 14;
+const DISTANCE_MASK: u16 = (1 << 14) - 1;
+
+pub struct Tablebase {
+    keys: Vec<u64>,
+    values: Vec<u16>
+}
+
+impl Tablebase {
+    // stateから到達可能な局面をsolverで解いて、終局表を作成します。stateは、全ての駒が確定している必要があります（確定した駒は確定したままなので、到達可能な局面も全て確定しています）。
+    //
+    // 終局表に含まれるのは、stateから到達可能な局面だけです。全ての駒が確定した合法な局面を全て含むわけではないので、量子の初期状態から到達する確定した局面の中には、終局表に含まれないものがあります（value()はNoneを返すので、ExternalSolver::solve_with_tablebase()はその先を探索します）。
+
+    pub fn generate(state: &State, solver: &ExternalSolver, on_progress: impl FnMut(&Progress)) -> io::Result<Tablebase> {
+        if !state.is_classical() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "tablebase states must be fully collapsed"));
+        }
+
+        Ok(Tablebase::from_solution(&solver.solve(state, None, on_progress)?))
+    }
+
+    // 解から、全ての駒が確定していて終局していない局面を取り出して、終局表を作成します。
+
+    pub fn from_solution(solution: &Solution) -> Tablebase {
+        let mut entries = solution
+            .iter()
+            .map(|(key, value)| (State::from_compact_key(key, 0).unwrap(), value))
+            .filter(|(state, _)| !RULES.outcome(state).is_over())
+            .filter_map(|(state, value)| classical_key(&state).map(|key| (key, encode(value))))
+            .collect::<Vec<_>>();
+
+        entries.sort_unstable();
+
+        let (keys, values) = entries.into_iter().unzip();
+
+        Tablebase { keys, values }
+    }
+
+    // solutionの局面のうち、全ての駒が確定していて、終局表に含まれない局面の数を数えます。ExternalSolver::solve_with_tablebase()は、これらの局面の先を終局表を使わずに探索しています。
+
+    pub fn count_missing(&self, solution: &Solution) -> u64 {
+        solution.iter().map(|(key, _)| State::from_compact_key(key, 0).unwrap()).filter(|state| state.is_classical() && self.value(state).is_none()).count() as u64
+    }
+
+    // 局面の数を取得します。
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // 局面の値を取得します。全ての駒が確定していない局面と、終局表に含まれない局面の場合は、Noneを返します。終局した局面は、終局表に含まれなくても値を返します。
+
+    pub fn value(&self, state: &State) -> Option<Value> {
+        if !state.is_classical() {
+            return None;
+        }
+
+        match RULES.outcome(state) {
+            Outcome::Win(_)  => return Some(Value::Win(0)),
+            Outcome::Loss(_) => return Some(Value::Loss(0)),
+            _                => ()
+        }
+
+        self.keys.binary_search(&classical_key(state)?).ok().map(|i| decode(self.values[i]).unwrap())
+    }
+
+    // ファイルに書き込みます。
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::with_capacity(BUFFER_SIZE, File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.keys.len() as u64).to_le_bytes())?;
+
+        for key in &self.keys {
+            writer.write_all(&key.to_le_bytes())?;
+        }
+
+        for value in &self.values {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        writer.flush()
+    }
+
+    // ファイルから読み込みます。
+
+    pub fn read(path: &Path) -> io::Result<Tablebase> {
+        let mut reader = BufReader::with_capacity(BUFFER_SIZE, File::open(path)?);
+
+        let mut magic = [0; 8];
+
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a tablebase file"));
+        }
+
+        let count = usize::try_from(u64::from_le_bytes(read_bytes(&mut reader)?)).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+        let keys = (0..count).map(|_| Ok(u64::from_le_bytes(read_bytes(&mut reader)?))).collect::<io::Result<Vec<_>>>()?;
+        let values = (0..count).map(|_| Ok(u16::from_le_bytes(read_bytes(&mut reader)?))).collect::<io::Result<Vec<_>>>()?;
+
+        if !keys.is_sorted_by(|key, next_key| key < next_key) || values.iter().any(|value| decode(*value).is_none()) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "corrupted tablebase file"));
+        }
+
+        Ok(Tablebase { keys, values })
+    }
+}
+
+// 固定長のバイト列を読み込みます。
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut result = [0; N];
+
+    reader.read_exact(&mut result)?;
+
+    Ok(result)
+}
+
+// 局面のキーを取得します。全ての駒が確定していない局面と、持ち駒にライオンがある局面の場合は、Noneを返します。
+
+fn classical_key(state: &State) -> Option<u64> {
+    if !state.is_classical() {
+        return None;
+    }
+
+    let key = |state: &State| {
+        (0..8).try_fold(0_u64, |acc, index| {
+            let animal = state.pieces[index].trailing_zeros() as u64;
+            let enemy = if state.ownership & 1 << index != 0 { 0 } else { 1 };
+
+            match state.bit_boards[index] {
+                0 if animal < 3 => Some(acc + (1 << (48 + 2 * (3 * enemy + animal)))),
+                0               => None,
+                bit_board       => Some(acc | ((animal + 1) | (enemy << 3)) << (4 * bit_board.trailing_zeros()))
+            }
+        })
+    };
+
+    Some(key(state)?.min(key(&state.mirrored())?))
+}
+
+// 値を、ファイルの形式にします。
+
+fn encode(value: Value) -> u16 {
+    match value {
+        Value::Win(distance) | Value::Loss(distance) if distance > DISTANCE_MASK => panic!("distance {} is too long for a tablebase", distance),
+        Value::Win(distance)                                                     => WIN | distance,
+        Value::Loss(distance)                                                    => LOSS | distance,
+        Value::Draw                                                              => 0
+    }
+}
+
+// ファイルの形式から、値を取得します。
+
+fn decode(value: u16) -> Option<Value> {
+    match value & !DISTANCE_MASK {
+        WIN                              => Some(Value::Win(value & DISTANCE_MASK)),
+        LOSS                             => Some(Value::Loss(value & DISTANCE_MASK)),
+        0 if value & DISTANCE_MASK == 0  => Some(Value::Draw),
+        _                                => None
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::ErrorKind;
+
+use quantum_animal_shogi_core::Game;
+use quantum_animal_shogi_solver::{ExternalEnumerator, Progress, count_in_memory, count_parallel};
+use tempfile::tempdir;
+
+// ディスクを使用して数えた結果が、メモリ上で数えた結果と一致することを確認します。ランのファイルが多数できるよう、run_capacityは小さくします。
+
+#[test]
+fn external_enumeration_matches_in_memory() {
+    for (state, max_depth) in [(Game::classical_initial_state(), 8), (Game::initial_state(), 4)] {
+        let mut expected_levels = Vec::new();
+        let expected_count = count_in_memory(&state, Some(max_depth), |level| expected_levels.push(*level));
+
+        let work_dir = tempdir().unwrap();
+        let enumerator = ExternalEnumerator { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+        let mut levels = Vec::new();
+        let count = enumerator.count(&state, Some(max_depth), |progress| if let Progress::Level(level) = progress { levels.push(*level) }).unwrap();
+
+        assert_eq!(count, expected_count);
+        assert_eq!(levels, expected_levels);
+        assert_eq!(levels.len() as u32, max_depth + 1);
+    }
+}
+
+// マルチ・スレッドで数えた結果が、シングル・スレッドで数えた結果と一致することを確認します。
+
+#[test]
+fn parallel_enumeration_matches_in_memory() {
+    for (state, max_depth) in [(Game::classical_initial_state(), 8), (Game::initial_state(), 4)] {
+        let mut expected_levels = Vec::new();
+        let expected_count = count_in_memory(&state, Some(max_depth), |level| expected_levels.push(*level));
+
+        let mut levels = Vec::new();
+        let count = count_parallel(&state, Some(max_depth), |level| levels.push(*level));
+
+        assert_eq!(count, expected_count);
+        assert_eq!(levels, expected_levels);
+    }
+}
+
+// 途中の深さで打ち切って再開した結果が、最後まで続けて数えた結果と一致することを確認します。
+
+#[test]
+fn external_enumeration_resumes_from_checkpoint() {
+    let state = Game::classical_initial_state();
+
+    let mut expected_levels = Vec::new();
+    let expected_count = count_in_memory(&state, Some(8), |level| expected_levels.push(*level));
+
+    let work_dir = tempdir().unwrap();
+    let enumerator = ExternalEnumerator { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+    let mut levels = Vec::new();
+
+    enumerator.count(&state, Some(4), |progress| if let Progress::Level(level) = progress { levels.push(*level) }).unwrap();
+
+    let count = enumerator.resume(&state, Some(8), |progress| if let Progress::Level(level) = progress { levels.push(*level) }).unwrap();
+
+    assert_eq!(count, expected_count);
+    assert_eq!(levels, expected_levels);
+
+    // 別の初期状態では、再開できません。
+
+    assert_eq!(enumerator.resume(&Game::initial_state(), Some(8), |_| ()).err().unwrap().kind(), ErrorKind::InvalidInput);
+}
+
+// どうぶつしょうぎの状態空間の大きさ（「どうぶつしょうぎ」の完全解析の、初期状態から到達可能な局面の数）です。時間がかかるので、`cargo test --release -- --ignored`で実行してください。
+
+#[test]
+#[ignore]
+fn classical_state_space() {
+    let work_dir = tempdir().unwrap();
+    let enumerator = ExternalEnumerator { work_dir: work_dir.path().to_path_buf(), run_capacity: 1 << 24 };
+
+    assert_eq!(enumerator.count(&Game::classical_initial_state(), None, |_| ()).unwrap(), 246_803_167);
+}
@@ -0,0 +1,45 @@
+use std::{fs, io::ErrorKind};
+
+use quantum_animal_shogi_solver::{KeyReader, KeyWriter};
+use tempfile::tempdir;
+
+// 書き込んだキーを、同じ順番で読み込めることを確認します。空のファイルは、キーが0個のファイルです。
+
+#[test]
+fn keys_round_trip() {
+    let work_dir = tempdir().unwrap();
+    let path = work_dir.path().join("keys");
+
+    let keys = [0, 1, u64::MAX as u128 + 1, u128::MAX];
+    let mut writer = KeyWriter::create(&path).unwrap();
+
+    for key in keys {
+        writer.write(key).unwrap();
+    }
+
+    assert_eq!(writer.finish().unwrap(), keys.len() as u64);
+    assert_eq!(fs::metadata(&path).unwrap().len(), 16 * keys.len() as u64);
+    assert_eq!(KeyReader::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap(), keys);
+
+    KeyWriter::create(&path).unwrap().finish().unwrap();
+
+    assert_eq!(KeyReader::open(&path).unwrap().count(), 0);
+}
+
+// キーの途中で終わっているファイルは、InvalidDataになることを確認します。
+
+#[test]
+fn truncated_keys_are_rejected() {
+    let work_dir = tempdir().unwrap();
+    let path = work_dir.path().join("keys");
+
+    let mut bytes = [1_u128.to_le_bytes(), 2_u128.to_le_bytes()].concat();
+
+    bytes.truncate(16 + 7);
+    fs::write(&path, &bytes).unwrap();
+
+    let mut reader = KeyReader::open(&path).unwrap();
+
+    assert_eq!(reader.next().unwrap().unwrap(), 1);
+    assert_eq!(reader.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,226 @@
+use std::{collections::HashMap, io::ErrorKind, panic};
+
+use proptest::{collection::vec, option, prelude::*};
+use quantum_animal_shogi_core::{Game, Outcome, RuleSet, State};
+use quantum_animal_shogi_solver::{ExternalSolver, Progress, Value, retrograde};
+use tempfile::tempdir;
+
+// ランダムなグラフを作成します。ノードごとに、種の値と次のノードです（重複や自己ループを含みます）。終局表の値を種にする場合に備えて、種には引き分けや任意の手数も含めます。
+
+fn graphs() -> impl Strategy<Value = Vec<(Option<Value>, Vec<u32>)>> {
+    (1..32_usize).prop_flat_map(|node_count| {
+        vec((option::weighted(0.2, prop_oneof![Just(Value::Win(0)), Just(Value::Loss(0)), Just(Value::Win(1)), (0..40_u16).prop_map(Value::Win), (0..40_u16).prop_map(Value::Loss), Just(Value::Draw)]), vec(0..node_count as u32, 0..4)), node_count)
+    })
+}
+
+// 手数ごとに値を決めていく、素朴な実装です。手数kの勝ちは次のノードに手数k - 1の負けがあるノード、手数kの負けは次のノードが全て手数k - 1以下の勝ちのノードです。
+
+fn naive_retrograde(graph: &[(Option<Value>, Vec<u32>)]) -> Vec<Value> {
+    let mut values = graph.iter().map(|(seed, children)| seed.or(children.is_empty().then_some(Value::Loss(0)))).collect::<Vec<_>>();
+
+    for k in 1..=graph.len() as u16 + 40 {
+        values = graph
+            .iter()
+            .zip(&values)
+            .map(|((_, children), value)| {
+                if value.is_some() {
+                    return *value;
+                }
+
+                if children.iter().any(|child| values[*child as usize] == Some(Value::Loss(k - 1))) {
+                    return Some(Value::Win(k));
+                }
+
+                if children.iter().all(|child| matches!(values[*child as usize], Some(Value::Win(distance)) if distance < k)) {
+                    return Some(Value::Loss(k));
+                }
+
+                None
+            })
+            .collect();
+    }
+
+    values.into_iter().map(|value| value.unwrap_or(Value::Draw)).collect()
+}
+
+proptest! {
+    // 後退解析の結果が、素朴な実装の結果と一致することを確認します。
+
+    #[test]
+    fn retrograde_matches_naive_implementation(graph in graphs()) {
+        let values = retrograde(graph.len(), |i| graph[i].0, |i| graph[i].1.clone());
+
+        prop_assert_eq!(values, naive_retrograde(&graph));
+    }
+}
+
+// 探索せずに値が決まる局面を解くと、その局面だけの解になることを確認します。
+
+#[test]
+fn decided_states_are_not_expanded() {
+    // 初期状態から、1手でライオンを取れる局面と、取られた局面を探します。
+
+    let (state, next_state) = successors(Game::classical_initial_state())
+        .flat_map(|state| successors(state).collect::<Vec<_>>())
+        .flat_map(|state| successors(state).collect::<Vec<_>>())
+        .filter(|state| Game::outcome(state) == Outcome::Ongoing)
+        .find_map(|state| successors(state).find(|next_state| matches!(Game::outcome(next_state), Outcome::Loss(_))).map(|next_state| (state, next_state)))
+        .unwrap();
+
+    for (state, value) in [(state, Value::Win(1)), (next_state, Value::Loss(0))] {
+        let work_dir = tempdir().unwrap();
+        let solution = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 }.solve(&state, None, |_| ()).unwrap();
+
+        assert_eq!(solution.len(), 1);
+        assert_eq!(solution.value(&state), Some(value));
+        assert_eq!(solution.value(&state.mirrored()), Some(value));
+        assert_eq!(solution.value(&Game::classical_initial_state()), None);
+    }
+}
+
+// 深さを制限して、ディスクを使用して解いた結果が、メモリ上で解いた結果と一致することを確認します。ランのファイルが多数できるよう、run_capacityは小さくします。
+
+#[test]
+fn external_solution_matches_in_memory() {
+    for (state, max_depth) in [(Game::classical_initial_state(), 8), (Game::initial_state(), 5)] {
+        let expected = solve_in_memory(&state, max_depth);
+
+        let work_dir = tempdir().unwrap();
+        let solver = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+        let mut levels = Vec::new();
+        let solution = solver.solve(&state, Some(max_depth), |progress| if let Progress::Level(level) = progress { levels.push(*level) }).unwrap();
+
+        assert_eq!(levels.last().unwrap().visited, expected.len() as u64);
+        assert_eq!(solution.len(), expected.len());
+        assert_eq!(solution.iter().collect::<HashMap<_, _>>(), expected);
+        assert_eq!(solution.value(&state), expected.get(&state.canonical_key()).copied());
+
+        // 値が決まった局面も、決まらなかった局面もあります。
+
+        assert!(expected.values().any(|value| *value != Value::Draw));
+        assert!(expected.values().any(|value| *value == Value::Draw));
+    }
+}
+
+// 途中で止まった後に再開した結果が、最後まで続けて解いた結果と一致することを確認します。進捗の報告でパニックさせて、列挙の途中と、値を決めている途中で止めます。
+
+#[test]
+fn external_solver_resumes_from_checkpoint() {
+    let state = Game::classical_initial_state();
+
+    let work_dir = tempdir().unwrap();
+    let solver = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+    let expected = solver.solve(&state, Some(6), |_| ()).unwrap().iter().collect::<Vec<_>>();
+
+    let stops: [fn(&Progress) -> bool; 2] = [
+        |progress| matches!(progress, Progress::Level(level) if level.depth == 3),
+        |progress| matches!(progress, Progress::Propagated { distance: 2, .. })
+    ];
+
+    for stop in stops {
+        assert!(panic::catch_unwind(|| solver.solve(&state, Some(6), |progress| assert!(!stop(progress), "stopped"))).is_err());
+
+        assert_eq!(solver.resume(&state, Some(6), |_| ()).unwrap().iter().collect::<Vec<_>>(), expected);
+    }
+
+    // 解き終わった後に再開すると、同じ解になります。
+
+    assert_eq!(solver.resume(&state, Some(6), |_| ()).unwrap().iter().collect::<Vec<_>>(), expected);
+
+    // 別の初期状態では、再開できません。
+
+    assert_eq!(solver.resume(&Game::initial_state(), Some(6), |_| ()).err().unwrap().kind(), ErrorKind::InvalidInput);
+}
+
+// 解き終わった作業用のディレクトリから解を開くと、解いた時と同じ値（量子的な局面の値も）を取得できることを確認します。
+
+#[test]
+fn solution_reopens_from_work_dir() {
+    let state = Game::initial_state();
+
+    let work_dir = tempdir().unwrap();
+    let solver = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+    // 解く前は、開けません。
+
+    assert_eq!(solver.solution().err().unwrap().kind(), ErrorKind::InvalidInput);
+
+    let expected = solver.solve(&state, Some(2), |_| ()).unwrap();
+    let solution = solver.solution().unwrap();
+
+    assert_eq!(solution.iter().collect::<Vec<_>>(), expected.iter().collect::<Vec<_>>());
+    assert_eq!(solution.value(&state), expected.value(&state));
+
+    let next_state = Game::next_state(&state, Game::legal_actions(&state).next().unwrap());
+
+    assert!(!next_state.is_classical());
+    assert_eq!(solution.value(&next_state), expected.value(&next_state));
+    assert!(solution.value(&next_state).is_some());
+
+    // 値を決めている途中で止まった場合は、開けません。
+
+    assert!(panic::catch_unwind(|| solver.solve(&Game::classical_initial_state(), Some(6), |progress| assert!(!matches!(progress, Progress::Propagated { distance: 2, .. }), "stopped"))).is_err());
+    assert_eq!(solver.solution().err().unwrap().kind(), ErrorKind::InvalidInput);
+}
+
+// 深さを制限して、メモリ上で解きます。探索せずに値が決まる局面は、ExternalSolverと同じく、終局した局面と1手で勝てる局面です。打ち切った深さの局面で、次の局面が探索されていないものは引き分けとします。
+
+fn solve_in_memory(state: &State, max_depth: u32) -> HashMap<u128, Value> {
+    let rules = RuleSet { max_turn: u16::MAX, repetition_count: 0, ..RuleSet::STANDARD };
+
+    let leaf_value = |state: &State| match rules.outcome(state) {
+        Outcome::Win(_)  => Some(Value::Win(0)),
+        Outcome::Loss(_) => Some(Value::Loss(0)),
+        _                => successors(*state).any(|next_state| matches!(rules.outcome(&next_state), Outcome::Loss(_))).then_some(Value::Win(1))
+    };
+
+    // 幅優先探索で、局面に番号を付けます。
+
+    let mut states = vec![*state];
+    let mut indices = HashMap::from([(state.canonical_key(), 0)]);
+    let mut frontier = 0..1;
+
+    for _ in 0..max_depth {
+        let end = states.len();
+
+        for i in frontier {
+            if leaf_value(&states[i]).is_some() {
+                continue;
+            }
+
+            for next_state in successors(states[i]) {
+                indices.entry(next_state.canonical_key()).or_insert_with(|| {
+                    states.push(next_state);
+                    states.len() - 1
+                });
+            }
+        }
+
+        frontier = end..states.len();
+    }
+
+    let children = states.iter().map(|state| successors(*state).map(|next_state| indices.get(&next_state.canonical_key()).map(|i| *i as u32)).collect::<Option<Vec<_>>>()).collect::<Vec<_>>();
+    let values = retrograde(states.len(), |i| leaf_value(&states[i]).or(children[i].is_none().then_some(Value::Draw)), |i| children[i].clone().unwrap());
+
+    states.iter().map(State::canonical_key).zip(values).collect()
+}
+
+// 次の局面のイテレーターを取得します。
+
+fn successors(state: State) -> impl Iterator<Item = State> {
+    Game::legal_actions(&state).map(move |action| Game::next_state(&state, action)).collect::<Vec<_>>().into_iter()
+}
+
+// どうぶつしょうぎは後手必勝です（「どうぶつしょうぎ」の完全解析）。時間とディスク（数十GB）がかかるので、`cargo test --release -- --ignored`で実行してください。
+
+#[test]
+#[ignore]
+fn classical_is_a_second_player_win() {
+    let state = Game::classical_initial_state();
+    let work_dir = tempdir().unwrap();
+    let solution = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1 << 26 }.solve(&state, None, |_| ()).unwrap();
+
+    assert!(matches!(solution.value(&state), Some(Value::Loss(_))));
+}
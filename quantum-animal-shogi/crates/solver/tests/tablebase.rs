@@ -0,0 +1,103 @@
+use std::{fs, io::ErrorKind};
+
+use quantum_animal_shogi_core::{Game, Outcome, State};
+use quantum_animal_shogi_solver::{ExternalSolver, Tablebase, Value};
+use tempfile::tempdir;
+
+// 解から作成した終局表を、ファイルに書き込んで読み込めることを確認します。
+
+#[test]
+fn tablebase_round_trips_through_file() {
+    let (state, next_state) = decided_states();
+
+    let work_dir = tempdir().unwrap();
+    let tablebase = Tablebase::generate(&state, &ExternalSolver { work_dir: work_dir.path().join("solver"), run_capacity: 1_000 }, |_| ()).unwrap();
+    let path = work_dir.path().join("classical.tb");
+
+    tablebase.write(&path).unwrap();
+
+    let tablebase = Tablebase::read(&path).unwrap();
+
+    assert_eq!(tablebase.len(), 1);
+    assert_eq!(tablebase.value(&state), Some(Value::Win(1)));
+    assert_eq!(tablebase.value(&state.mirrored()), Some(Value::Win(1)));
+
+    // 終局した局面は、終局表に含まれなくても値が分かります。
+
+    assert_eq!(tablebase.value(&next_state), Some(Value::Loss(0)));
+
+    // 終局表に含まれない局面と、全ての駒が確定していない局面は、値が分かりません。
+
+    assert_eq!(tablebase.value(&Game::classical_initial_state()), None);
+    assert_eq!(tablebase.value(&Game::initial_state()), None);
+}
+
+// 終局表に含まれない確定した局面を、数えられることを確認します。
+
+#[test]
+fn missing_states_are_counted() {
+    let (state, _) = decided_states();
+
+    let work_dir = tempdir().unwrap();
+    let solver = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+    let tablebase = Tablebase::generate(&state, &solver, |_| ()).unwrap();
+
+    assert_eq!(tablebase.count_missing(&solver.solve(&state, None, |_| ()).unwrap()), 0);
+
+    // 初期状態から2手以内の局面は終局していないので、全て終局表に含まれません。
+
+    let solution = solver.solve(&Game::classical_initial_state(), Some(2), |_| ()).unwrap();
+
+    assert_eq!(tablebase.count_missing(&solution), solution.len() as u64);
+}
+
+// 終局表ではないファイルを、読み込めないことを確認します。
+
+#[test]
+fn invalid_files_are_rejected() {
+    let work_dir = tempdir().unwrap();
+    let path = work_dir.path().join("invalid.tb");
+
+    fs::write(&path, b"not a tablebase").unwrap();
+
+    assert_eq!(Tablebase::read(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+}
+
+// 初期状態から、1手でライオンを取れる局面と、取られた局面を探します。
+
+fn decided_states() -> (State, State) {
+    successors(Game::classical_initial_state())
+        .flat_map(|state| successors(state).collect::<Vec<_>>())
+        .flat_map(|state| successors(state).collect::<Vec<_>>())
+        .filter(|state| Game::outcome(state) == Outcome::Ongoing)
+        .find_map(|state| successors(state).find(|next_state| matches!(Game::outcome(next_state), Outcome::Loss(_))).map(|next_state| (state, next_state)))
+        .unwrap()
+}
+
+// 次の局面のイテレーターを取得します。
+
+fn successors(state: State) -> impl Iterator<Item = State> {
+    Game::legal_actions(&state).map(move |action| Game::next_state(&state, action)).collect::<Vec<_>>().into_iter()
+}
+
+// 全ての駒が確定していない局面からは、終局表を作成できないことを確認します。
+
+#[test]
+fn quantum_states_are_rejected() {
+    let work_dir = tempdir().unwrap();
+    let solver = ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1_000 };
+
+    assert_eq!(Tablebase::generate(&Game::initial_state(), &solver, |_| ()).err().unwrap().kind(), ErrorKind::InvalidInput);
+}
+
+// 「どうぶつしょうぎ」の終局表を作成して、初期状態が後手必勝であることを確認します。時間とディスク（数十GB）がかかるので、`cargo test --release -- --ignored`で実行してください。
+
+#[test]
+#[ignore]
+fn classical_tablebase() {
+    let state = Game::classical_initial_state();
+    let work_dir = tempdir().unwrap();
+    let tablebase = Tablebase::generate(&state, &ExternalSolver { work_dir: work_dir.path().to_path_buf(), run_capacity: 1 << 26 }, |_| ()).unwrap();
+
+    assert!(matches!(tablebase.value(&state), Some(Value::Loss(_))));
+}
@@ -1,8 +1,9 @@
+use arrayvec::ArrayVec;
 use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
-use quantum_animal_shogi_core::{Game, State as State_};
+use quantum_animal_shogi_core::{Action as Action_, DrawReason as DrawReason_, Game, ObservationLayout, Outcome as Outcome_, Reason as Reason_, Square, State as State_};
 
 // #[wasm_bindgen]
 // extern "C" {
@@ -38,16 +39,78 @@ impl State {
     }
 }
 
-#[derive(Tsify, Serialize, Deserialize)]
+// アクションです。TypeScriptでは{ kind: 'move', from, to }か{ kind: 'drop', handSlot, to }になります。マスはビット・ボードのビットの位置（手番側から見て右下が0。Square::bit()）で、handSlotは手番側の持ち駒の中での順番です。coreのActionを、TypeScriptで扱いやすい形にしたもので、coreのActionを経由して変換します。
+
+#[derive(Clone, Copy, Tsify, Serialize, Deserialize)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
-pub struct Action(u8, u8);
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Action {
+    Move { from: u8, to: u8 },
+    Drop { hand_slot: u8, to: u8 }
+}
 
-impl From<(u8, u8)> for Action {
-    fn from(action: (u8, u8)) -> Action {
-        Action(action.0, action.1)
+impl From<Action_> for Action {
+    fn from(action: Action_) -> Action {
+        match action {
+            Action_::Move { from, to }      => Action::Move { from: from.bit(), to: to.bit() },
+            Action_::Drop { hand_slot, to } => Action::Drop { hand_slot, to: to.bit() }
+        }
+    }
+}
+
+impl TryFrom<Action> for Action_ {
+    type Error = JsError;
+
+    fn try_from(action: Action) -> Result<Action_, JsError> {
+        match action {
+            Action::Move { from, to }      => Ok(Action_::Move { from: square(from)?, to: square(to)? }),
+            Action::Drop { hand_slot, to } => Ok(Action_::Drop { hand_slot: hand_slot_or_piece_id(hand_slot)?, to: square(to)? })
+        }
+    }
+}
+
+// 安定した表現のアクションです。TypeScriptでは{ kind: 'move', from, to }か{ kind: 'drop', pieceId, to }になります。持ち駒を打つアクションは、持ち駒の中での順番の代わりに駒の番号（State::piece_id()）を使うので、持ち駒が増えたり減ったりしても変わりません。観測結果（"rows-v2"）の持ち駒の行と対応します。coreでは安定した表現もActionで表す（Action::Dropのhand_slotが駒の番号になる）ので、coreのActionを経由して変換します。
+
+#[derive(Clone, Copy, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum StableAction {
+    Move { from: u8, to: u8 },
+    Drop { piece_id: u8, to: u8 }
+}
+
+impl From<Action_> for StableAction {
+    fn from(action: Action_) -> StableAction {
+        match action {
+            Action_::Move { from, to }      => StableAction::Move { from: from.bit(), to: to.bit() },
+            Action_::Drop { hand_slot, to } => StableAction::Drop { piece_id: hand_slot, to: to.bit() }
+        }
     }
 }
 
+impl TryFrom<StableAction> for Action_ {
+    type Error = JsError;
+
+    fn try_from(action: StableAction) -> Result<Action_, JsError> {
+        match action {
+            StableAction::Move { from, to }     => Ok(Action_::Move { from: square(from)?, to: square(to)? }),
+            StableAction::Drop { piece_id, to } => Ok(Action_::Drop { hand_slot: hand_slot_or_piece_id(piece_id)?, to: square(to)? })
+        }
+    }
+}
+
+// TypeScriptから渡されたマスを、Squareに変換します。
+
+fn square(bit: u8) -> Result<Square, JsError> {
+    Square::new(bit).ok_or_else(|| JsError::new(&format!("invalid square: {}", bit)))
+}
+
+// TypeScriptから渡された持ち駒の順番か駒の番号が、範囲内（0〜7）であることをチェックします。
+
+fn hand_slot_or_piece_id(value: u8) -> Result<u8, JsError> {
+    if value < 8 { Ok(value) } else { Err(JsError::new(&format!("invalid hand slot or piece id: {}", value))) }
+}
+
 #[wasm_bindgen(js_name = getInitialState)]
 pub fn get_initial_state() -> State {
     State {
@@ -55,39 +118,115 @@ pub fn get_initial_state() -> State {
     }
 }
 
+#[wasm_bindgen(js_name = getClassicalInitialState)]
+pub fn get_classical_initial_state() -> State {
+    State {
+        state: Game::classical_initial_state()
+    }
+}
+
 #[wasm_bindgen(js_name = getTurnedState)]
 pub fn get_turned_state(state: &State) -> State {
-    let mut result = state.state.clone();
+    let mut result = state.state;
 
     result.ownership = !result.ownership;
     result.bit_boards = result.bit_boards.map(|bit_board| bit_board.reverse_bits() >> 4);
+    result.hash = result.compute_hash();
 
     State { state: result }
 }
 
 #[wasm_bindgen(js_name = getLegalActions)]
 pub fn get_legal_actions(state: &State) -> Vec<Action> {
-    Game::legal_actions(&state.state).map(|action| action.into()).collect()
+    Game::legal_actions(&state.state).flat_map(Action_::try_from).map(Action::from).collect()
 }
 
 #[wasm_bindgen(js_name = getNextState)]
-pub fn get_next_state(state: &State, action: &Action) -> State {
-    State { state: Game::next_state(&state.state, (action.0, action.1)) }
+pub fn get_next_state(state: &State, action: &Action) -> Result<State, JsError> {
+    Ok(State { state: Game::try_next_state(&state.state, Action_::try_from(*action)?.into())? })
 }
 
-#[wasm_bindgen]
-pub fn won(state: &State) -> bool {
-    Game::won(&state.state)
+// アクションを、安定した表現に変換します。handSlotの持ち駒がない場合は、undefinedを返します。
+
+#[wasm_bindgen(js_name = toStableAction)]
+pub fn to_stable_action(state: &State, action: &Action) -> Result<Option<StableAction>, JsError> {
+    Ok(state.state.to_stable_action(Action_::try_from(*action)?.into()).and_then(|action| Action_::try_from(action).ok()).map(StableAction::from))
 }
 
-#[wasm_bindgen]
-pub fn lost(state: &State) -> bool {
-    Game::lost(&state.state)
+// 安定した表現を、アクションに変換します。駒が手番側の持ち駒ではない場合は、undefinedを返します。
+
+#[wasm_bindgen(js_name = fromStableAction)]
+pub fn from_stable_action(state: &State, action: &StableAction) -> Result<Option<Action>, JsError> {
+    Ok(state.state.from_stable_action(Action_::try_from(*action)?.into()).and_then(|action| Action_::try_from(action).ok()).map(Action::from))
 }
 
-#[wasm_bindgen]
-pub fn draw(state: &State) -> bool {
-    Game::draw(&state.state)
+// 観測結果を取得します。layoutには"rows-v1"か"rows-v2"か"planes-v1"を指定してください（ObservationLayoutを参照）。形がgetObservationShape()の配列を、行優先で1次元にした配列です。
+
+#[wasm_bindgen(js_name = getObservation)]
+pub fn get_observation(state: &State, layout: &str) -> Result<Vec<f32>, JsError> {
+    Ok(layout.parse::<ObservationLayout>()?.encode(&state.state))
+}
+
+#[wasm_bindgen(js_name = getObservationShape)]
+pub fn get_observation_shape(layout: &str) -> Result<Vec<u32>, JsError> {
+    Ok(layout.parse::<ObservationLayout>()?.shape().iter().map(|size| *size as u32).collect())
+}
+
+// 駒の種類ごとの確率（全ての可能世界が同じ確率だとした場合）を取得します。[駒（8）×駒の種類（5）]を1次元にした配列です。
+
+#[wasm_bindgen(js_name = getProbabilities)]
+pub fn get_probabilities(state: &State) -> Vec<f64> {
+    let marginals = state.state.marginals();
+
+    (0..8).flat_map(|index| (0..5).map(move |piece_bit| marginals.probability(index, piece_bit))).collect()
+}
+
+// ゲームの結果です。手番側から見た結果になります。
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "kind", content = "reason", rename_all = "camelCase")]
+pub enum Outcome {
+    Ongoing,
+    Win(Reason),
+    Loss(Reason),
+    Draw(DrawReason)
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Reason {
+    Catch,
+    Try
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DrawReason {
+    TurnLimit,
+    Repetition
+}
+
+impl From<Outcome_> for Outcome {
+    fn from(outcome: Outcome_) -> Outcome {
+        let convert = |reason| match reason {
+            Reason_::Catch => Reason::Catch,
+            Reason_::Try   => Reason::Try
+        };
+
+        match outcome {
+            Outcome_::Ongoing                       => Outcome::Ongoing,
+            Outcome_::Win(reason)                   => Outcome::Win(convert(reason)),
+            Outcome_::Loss(reason)                  => Outcome::Loss(convert(reason)),
+            Outcome_::Draw(DrawReason_::TurnLimit)  => Outcome::Draw(DrawReason::TurnLimit),
+            Outcome_::Draw(DrawReason_::Repetition) => Outcome::Draw(DrawReason::Repetition)
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = getOutcome)]
+pub fn get_outcome(state: &State) -> Outcome {
+    Game::outcome(&state.state).into()
 }
 
 fn get_score(state: &State_) -> i32 {
@@ -114,17 +253,12 @@ fn get_score(state: &State_) -> i32 {
     ally_piece_advantage_score - enemy_piece_advantage_score
 }
 
-fn alpha_beta(state: &State_, depth: i32, alpha: i32, beta: i32) -> (i32, Option<(u8, u8)>) {
-    if Game::won(state) {
-        return ( 1_000 + depth, None)
-    }
-
-    if Game::lost(state) {
-        return (-1_000 - depth, None)
-    }
-
-    if Game::draw(state) {
-        return ( -500, None)
+fn alpha_beta(state: &mut State_, depth: i32, alpha: i32, beta: i32) -> (i32, Option<(u8, u8)>) {
+    match Game::outcome(state) {
+        Outcome_::Win(_)  => return ( 1_000 + depth, None),
+        Outcome_::Loss(_) => return (-1_000 - depth, None),
+        Outcome_::Draw(_) => return ( -500, None),
+        Outcome_::Ongoing => ()
     }
 
     if depth == 0 {
@@ -134,9 +268,12 @@ fn alpha_beta(state: &State_, depth: i32, alpha: i32, beta: i32) -> (i32, Option
     let mut alpha = alpha;
     let mut action = None;
 
-    for action_prime in Game::legal_actions(state) {
-        let state_prime = Game::next_state(state, action_prime);
-        let alpha_prime = -alpha_beta(&state_prime, depth - 1, -beta, -alpha).0;
+    // ステートのコピーを避けるため、make_move()とunmake_move()で探索します。探索中はステートが変わるので、合法手を先に取得しておきます。同じ結果になる持ち駒を打つアクションは、探索しません。
+
+    for action_prime in Game::distinct_legal_actions(state).collect::<ArrayVec<_, 128>>() {
+        let undo = Game::make_move(state, action_prime);
+        let alpha_prime = -alpha_beta(state, depth - 1, -beta, -alpha).0;
+        Game::unmake_move(state, undo);
 
         if alpha_prime > alpha {
             alpha = alpha_prime;
@@ -152,6 +289,6 @@ fn alpha_beta(state: &State_, depth: i32, alpha: i32, beta: i32) -> (i32, Option
 }
 
 #[wasm_bindgen(js_name = getAction)]
-pub fn get_action(state: &State, depth: i32) -> Action {
-    alpha_beta(&state.state, depth, -9_999, 9_999).1.unwrap().into()
+pub fn get_action(state: &State, depth: i32) -> Result<Action, JsError> {
+    Ok(Action_::try_from(alpha_beta(&mut state.state.clone(), depth, -9_999, 9_999).1.unwrap())?.into())
 }
@@ -23,7 +23,6 @@ class MCTS():
         self.Ns = {}  # stores #times board s was visited
         self.Ps = {}  # stores initial policy (returned by neural net)
 
-        self.Es = {}  # stores game.getGameEnded ended for board s
         self.Vs = {}  # stores game.getValidMoves for board s
 
         self.rng = np.random.default_rng(1234)
@@ -77,11 +76,11 @@ class MCTS():
 
         s = self.game.stringRepresentation(canonicalBoard)
 
-        if s not in self.Es:
-            self.Es[s] = self.game.getGameEnded(canonicalBoard, 1)
-        if self.Es[s] != 0:
+        # The game end is not cached by s, since draws by repetition depend on the history leading to the board.
+        e = self.game.getGameEnded(canonicalBoard, 1)
+        if e != 0:
             # terminal node
-            return -self.Es[s]
+            return -e
 
         if s not in self.Ps:
             # leaf node
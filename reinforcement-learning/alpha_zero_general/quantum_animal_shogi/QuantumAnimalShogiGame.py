@@ -1,3 +1,5 @@
+import numpy as np
+
 from copy import copy
 
 from ..Game import Game
@@ -14,7 +16,7 @@ class QuantumAnimalShogiGame(Game):
         return env_prime
 
     def _get_action_mask(self, env):
-        return env.observe()["action_mask"]
+        return env.observe("rows-v1")["action_mask"]  # "action_mask"は、レイアウトによらず同じです。
 
     def _get_game_ended(self, env):
         if env.won():
@@ -50,7 +52,7 @@ class QuantumAnimalShogiGame(Game):
         return board
 
     def getSymmetries(self, board, policy):
-        return [(board, policy)]  # 効率が悪くなるけど、とりあえず反転は無視で。。。
+        return [(board, policy), (board.mirrored(), list(RawEnvironment.mirror_policy(np.asarray(policy, dtype=np.float32))))]  # 盤面を左右反転して、学習データを水増しします。
 
     def stringRepresentation(self, board):
-        return str(board)  # 無駄に長い気がするけど、とりあえずこれで。。。
+        return (board.compact_key(), board.turn())  # ハッシュ値は衝突する場合があるので、正確なキーを使います。max_turnによる引き分けがあるので、手数も含めます。
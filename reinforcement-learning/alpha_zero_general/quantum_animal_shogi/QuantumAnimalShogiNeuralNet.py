@@ -5,6 +5,7 @@ import torch.nn as nn
 import torch.nn.functional as F
 import torch.optim as optim
 
+from quantum_animal_shogi import RawEnvironment
 from tqdm import tqdm
 
 from ..NeuralNet import NeuralNet
@@ -14,6 +15,8 @@ from ..utils import AverageMeter
 NUMBER_OF_CHANNELS = 512
 DROPOUT_RATIO = 0.5
 
+OBSERVATION_LAYOUT = "planes-v1"  # CNN向けの[チャンネル, 4, 3]のレイアウトです。持ち駒の特徴は、全てのマスに複製されています。
+
 NUMBER_OF_EPOCHS = 10
 BATCH_SIZE = 64
 
@@ -25,7 +28,7 @@ class NNModule(nn.Module):
     def __init__(self, game):
         super().__init__()
 
-        self.c_conv_1 = nn.Conv2d(1 * (5 + 2 + 2) + 7 * (5 + 2 + 2 + 8), NUMBER_OF_CHANNELS, 3, stride=1, padding=1)
+        self.c_conv_1 = nn.Conv2d(RawEnvironment.observation_shape(OBSERVATION_LAYOUT)[0], NUMBER_OF_CHANNELS, 3, stride=1, padding=1)
         self.c_conv_2 = nn.Conv2d(NUMBER_OF_CHANNELS, NUMBER_OF_CHANNELS, 3, stride=1, padding=1)
         self.c_conv_3 = nn.Conv2d(NUMBER_OF_CHANNELS, NUMBER_OF_CHANNELS, 3, stride=1, padding=1)
         self.c_conv_4 = nn.Conv2d(NUMBER_OF_CHANNELS, NUMBER_OF_CHANNELS, 3, stride=1, padding=1)
@@ -69,28 +72,9 @@ class QuantumAnimalShogiNeuralNet(NeuralNet):
         self.nn_module = NNModule(self.game).to(device)
 
     def env_to_x(self, env):
-        # 観測結果を取得します。
-
-        observation = env.observe()["observation"]
-
-        # 観測結果を入力に変換します。CUDAのことも考えると先にtorchのテンソルに変換した方がよいのだけど、とりあえず、NumPyで。。。
-
-        board = observation[:4 * 3]
-
-        hand = observation[4 * 3:]
-        hand_indices = np.lexsort(hand.T)
-        hand = np.ravel(np.concat([hand[hand_indices][1:], np.eye(8, dtype=np.float32)[hand_indices][1:]], axis=1))
-        hand = np.broadcast_to(hand[None, :], [12, 119])
-
-        x = np.permute_dims(
-            np.reshape(
-                np.concatenate([board, hand], axis=1),
-                [4, 3, 1 * (5 + 2 + 2) + 7 * (5 + 2 + 2 + 8)]
-            ),
-            [2, 0, 1]
-        )
+        # 観測結果を取得して、そのまま入力にします。
 
-        return torch.from_numpy(x)
+        return torch.from_numpy(env.observe(OBSERVATION_LAYOUT)["observation"])
 
     def predict(self, env):
         # 入力を作成します。
@@ -21,7 +21,7 @@ mcts = MCTS(game, neural_net, args)
 
 
 def get_action(observation):
-    return np.argmax(mcts.getActionProb(raw_environment_from_observation(observation), temp=0))
+    return np.argmax(mcts.getActionProb(raw_environment_from_observation(observation, "rows-v2"), temp=0))
 
 
 if __name__ == "__main__":
@@ -67,9 +67,9 @@ def alpha_beta(raw_env, depth, alpha, beta):
         return -float_info.max / 2, None
         # return -500, None
 
-    # 観測を実施します。
+    # 観測を実施します。get_score()の列のインデックスは、"rows-v1"のレイアウト（[マスと持ち駒、駒の特徴]）のものです。
 
-    observation = raw_env.observe()
+    observation = raw_env.observe("rows-v1")
 
     board, hand = np.split(observation["observation"], [4 * 3])
     action_mask = observation["action_mask"]
@@ -120,7 +120,7 @@ def alpha_beta(raw_env, depth, alpha, beta):
 # アルファ・ベータ法でアクションを選択します。
 
 def get_action(observation):
-    alpha, action = alpha_beta(raw_environment_from_observation(observation), MAX_DEPTH, -inf, inf)
+    alpha, action = alpha_beta(raw_environment_from_observation(observation, "rows-v2"), MAX_DEPTH, -inf, inf)
 
     # ログは、標準エラー出力に出力してください。
 
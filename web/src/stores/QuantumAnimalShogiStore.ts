@@ -1,22 +1,31 @@
 import { defineStore } from 'pinia'
-import { getAction, getInitialState, getLegalActions, getNextState, getTurnedState, won, lost, draw } from 'quantum-animal-shogi-webasm'
-import type { Action, State } from 'quantum-animal-shogi-webasm'
+import { fromStableAction, getAction, getInitialState, getLegalActions, getNextState, getObservation, getObservationShape, getTurnedState, getOutcome, toStableAction } from 'quantum-animal-shogi-webasm'
+import type { Action, StableAction, State } from 'quantum-animal-shogi-webasm'
 import { computed, nextTick, ref } from 'vue'
-import { filterMap, map, pipe } from 'rambda'
+import { map, pipe } from 'rambda'
 import chickUrl from '@/assets/chick.bmp'
 import chickenUrl from '@/assets/chicken.bmp'
 import elephantUrl from '@/assets/elephant.bmp'
 import giraffeUrl from '@/assets/giraffe.bmp'
 import lionUrl from '@/assets/lion.bmp'
 
-function* getBits(x: number): Iterable<number> {
-  while (x) {
-    yield 31 - Math.clz32(x & -x)
+// 観測結果のレイアウトです。行は0〜11が盤面のマス（手番側から見て左上が0）、12〜19が持ち駒（12 + 駒の番号）で、列は0〜4が駒の可能性、5〜6が由来（先手、後手）、7〜8が所有者（手番側、敵）です。持ち駒の行を安定したアクションと対応させるので、"rows-v2"を使用します。
 
-    x &= x - 1
-  }
+const OBSERVATION_LAYOUT = 'rows-v2'
+
+const getRows = (state: State) => {
+  const [rowCount, featureCount] = [...getObservationShape(OBSERVATION_LAYOUT)] as [number, number]
+  const observation = [...getObservation(state, OBSERVATION_LAYOUT)]
+
+  return Array.from({ length: rowCount }, (_, i) => observation.slice(i * featureCount, (i + 1) * featureCount))
 }
 
+// 画面のマスの番号です。盤面のマスはビット・ボードのビットの位置（右下が0）、持ち駒は観測結果の行と同じ12 + 駒の番号です。
+
+export const getSourceCell = (action: StableAction) => action.kind === 'move' ? action.from : 4 * 3 + action.pieceId
+
+const getStableAction = (sourceCell: number, to: number): StableAction => sourceCell < 4 * 3 ? { kind: 'move', from: sourceCell, to } : { kind: 'drop', pieceId: sourceCell - 4 * 3, to }
+
 export const useQuantumAnimalShogiStore = defineStore('state', () => {
   const state        = ref(getInitialState())
   const depth        = ref(8)
@@ -44,46 +53,24 @@ export const useQuantumAnimalShogiStore = defineStore('state', () => {
     )
   }
 
-  const setPieceState = (state: State, pieceIndex: number, pieceState: number[]) => {
-    for (const bit of getBits(state.pieces[pieceIndex]!)) {
-      pieceState[bit] = 1
-    }
-
-    pieceState[5] = pieceIndex < 4 ? 1 : 0
-    pieceState[5 + 1] = state.ownership & (1 << pieceIndex) ? 1 : 0
-  }
+  // 盤面を、ビット・ボードのビットの順（右下が0）で取得します。
 
   const getBoard = (state: State) => {
-    const result = Array.from({ length: 4 * 3 }, () => Array.from({ length: 5 + 2 }, () => 0))
-
-    for (const [pieceIndex, bitBoard] of pipe(
-      [...state.bitBoards],
-      filterMap((bitBoard, pieceIndex) => bitBoard !== 0 ? [pieceIndex, bitBoard] as [number, number] : null)
-    )) {
-      setPieceState(state, pieceIndex, result[(31 - Math.clz32(bitBoard & -bitBoard))]!)
-    }
+    const rows = getRows(state)
 
-    return result
+    return Array.from({ length: 4 * 3 }, (_, bit) => rows[4 * 3 - 1 - bit]!)
   }
 
-  const getHands = (state: State, ownership: boolean) => {
-    const result = Array.from({ length: 8 }, () => Array.from({ length: 5 + 1 + 1 }, () => 0))
-
-    for (const [pieceIndex, i] of pipe(
-      [...state.bitBoards],
-      filterMap((bitBoard, pieceIndex) => !bitBoard && ((state.ownership & (1 << pieceIndex)) !== 0) === ownership ? {value: pieceIndex} : null),
-      map((pieceIndex, i): [number, number] => [pieceIndex.value, i])
-    )) {
-      setPieceState(state, pieceIndex, result[i]!)
-    }
+  // 持ち駒を、駒の番号の順で取得します。持ち駒を打つアクションは、安定した表現（駒の番号）になります。
 
-    return result
+  const getHands = (state: State, ownership: boolean) => {
+    return getRows(state).slice(4 * 3).map(row => row[5 + 2 + (ownership ? 0 : 1)] ? row : row.map(() => 0))
   }
 
   const board        = computed(() => getBoard(isMyTurn.value ? state.value : getTurnedState(state.value)))
   const allyHands    = computed(() => getHands(isMyTurn.value ? state.value : getTurnedState(state.value), true ))
   const enemyHands   = computed(() => getHands(isMyTurn.value ? state.value : getTurnedState(state.value), false))
-  const legalActions = computed(() => getLegalActions(state.value))
+  const legalActions = computed(() => getLegalActions(state.value).flatMap(action => toStableAction(state.value, action) ?? []))  // 合法手の持ち駒は必ずあるので、[]にはなりません。
 
   const reset = () => {
     state.value = getInitialState()
@@ -95,16 +82,18 @@ export const useQuantumAnimalShogiStore = defineStore('state', () => {
     state.value = getNextState(state.value, action)
     isMyTurn.value = !isMyTurn.value
 
-    if (won(state.value)) {
-      reward.value = isMyTurn.value ? 1 : -1
-    }
+    switch (getOutcome(state.value).kind) {
+      case 'win':
+        reward.value = isMyTurn.value ? 1 : -1
+        break
 
-    if (lost(state.value)) {
-      reward.value = isMyTurn.value ? -1 : 1
-    }
+      case 'loss':
+        reward.value = isMyTurn.value ? -1 : 1
+        break
 
-    if (draw(state.value)) {
-      reward.value = -0.5
+      case 'draw':
+        reward.value = -0.5
+        break
     }
 
     await nextTick()
@@ -112,7 +101,7 @@ export const useQuantumAnimalShogiStore = defineStore('state', () => {
   }
 
   const executeAction = async () => {
-    const action = [action0.value!, action1.value!] as [number, number]
+    const action = fromStableAction(state.value, getStableAction(action0.value!, action1.value!))!
 
     action0.value = null
     action1.value = null